@@ -2,191 +2,466 @@ use crate::*;
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse::{Parse, ParseStream};
-use syn::punctuated::Punctuated;
-use syn::{braced, Token};
 
 pub struct ArgumentsInput {
-    _struct_token: Token![struct],
     ident: syn::Ident,
-    _brace_token: syn::token::Brace,
+    generics: syn::Generics,
     fields: ArgumentFields,
+    deserialize: bool,
 }
 
 impl ArgumentsInput {
     pub fn gen(&self) -> TokenStream {
-        let parse_arms = self.fields.arguments.iter().map(|argument| {
+        let fields = &self.fields;
+        let position = fields.variable_position;
+        let num_after = fields.arguments.len() - position;
+        // The arguments after the variable argument are taken from `rest`.
+        let parse_arm = |argument: &Field, args: proc_macro2::TokenStream| {
             let ident = &argument.ident;
+            let value = argument.parse(args);
             quote! {
-                #ident:
-                    entrance::parse_argument(
-                        args
-                            .next()
-                            .ok_or(entrance::Error::InvalidNumberOfArguments)?
-                    )?,
+                #ident: #value,
             }
-        });
+        };
+        let parse_arms = fields.arguments[..position]
+            .iter()
+            .map(|argument| parse_arm(argument, quote! { *args }));
+        let parse_arms_after = fields.arguments[position..]
+            .iter()
+            .map(|argument| parse_arm(argument, quote! { rest }));
 
-        let parse_var_arg = self.fields.variable_argument.as_ref().map(|argument| {
+        let parse_var_arg = fields.variable_argument.as_ref().map(|argument| {
             let ident = &argument.ident;
+            let assign_rest = if num_after > 0 {
+                Some(quote! { rest = remaining; })
+            } else {
+                None
+            };
             quote! {
-                #ident: entrance::parse_variable_argument(args)?,
+                #ident: {
+                    let spec = <Self as entrance::Arguments>::var_spec().unwrap();
+                    let after = <Self as entrance::Arguments>::spec()[#position..]
+                        .iter()
+                        .map(|arg| arg.num_values)
+                        .sum();
+                    let (values, remaining) =
+                        entrance::split_variable_argument(args, after, &spec)?;
+                    #assign_rest
+                    entrance::parse_variable_argument(values.into_iter())?
+                },
             }
         });
+        let declare_rest = if fields.variable_argument.is_some() && num_after > 0 {
+            Some(quote! { let mut rest; })
+        } else {
+            None
+        };
 
-        let names = self.fields.arguments.iter().map(|argument| &argument.ident);
-        let descriptions = self
+        let specs = self
             .fields
             .arguments
             .iter()
-            .map(|argument| &argument.description);
+            .map(|argument| argument.spec(&self.generics));
 
         let var_spec_impl = if let Some(argument) = &self.fields.variable_argument {
-            let ident = &argument.ident;
-            let description = &argument.description;
+            let spec = argument.spec(&self.generics);
             quote! {
-                Some(entrance::Arg {
-                    name: stringify!(#ident),
-                    description: #description,
-                })
+                Some(#spec)
             }
         } else {
             quote! { None }
         };
 
         let ident = &self.ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
         let num_arguments = self.fields.arguments.len();
+        let deserialize_impl = if self.deserialize {
+            Some(self.gen_deserialize())
+        } else {
+            None
+        };
         (quote! {
-            impl entrance::Arguments for #ident {
+            impl #impl_generics entrance::Arguments for #ident #ty_generics #where_clause {
                 fn parse<I: std::iter::Iterator<Item = std::string::String>>(
                     args: &mut I
                 ) -> entrance::Result<Self> {
+                    #declare_rest
                     Ok(Self {
                         #(
                             #parse_arms
                         )*
                         #parse_var_arg
+                        #(
+                            #parse_arms_after
+                        )*
                     })
                 }
 
-                fn spec() -> &'static [entrance::Arg] {
-                    const ARGS: [entrance::Arg; #num_arguments] = [
+                fn spec() -> &'static [entrance::Arg<'static>] {
+                    const ARGS: [entrance::Arg<'static>; #num_arguments] = [
                         #(
-                            entrance::Arg{
-                                name: stringify!(#names),
-                                description: #descriptions,
-                            },
+                            #specs,
                         )*
                     ];
                     &ARGS
                 }
 
-                fn var_spec() -> std::option::Option<entrance::Arg> {
+                fn var_spec() -> std::option::Option<entrance::Arg<'static>> {
                     #var_spec_impl
                 }
+
+                fn var_position() -> usize {
+                    #position
+                }
             }
+
+            #deserialize_impl
         })
         .into()
     }
+
+    /// Generates `serde::Deserialize` parsing each value like a command line argument.
+    ///
+    /// The impl is wrapped in `entrance::__requires_serde!`, which fails to compile without the
+    /// `serde` feature of entrance.
+    fn gen_deserialize(&self) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        let mut generics = self.generics.clone();
+        generics.params.insert(0, syn::parse_quote!('de));
+        let (impl_generics, _, _) = generics.split_for_impl();
+        let (_, ty_generics, where_clause) = self.generics.split_for_impl();
+        quote! {
+            entrance::__requires_serde! {
+                impl #impl_generics entrance::serde::Deserialize<'de> for #ident #ty_generics
+                    #where_clause
+                {
+                    fn deserialize<D: entrance::serde::Deserializer<'de>>(
+                        deserializer: D
+                    ) -> std::result::Result<Self, D::Error> {
+                        entrance::deserialize_arguments(deserializer)
+                    }
+                }
+            }
+        }
+    }
+}
+
+const STRUCT_KEYS: &[&str] = &["deserialize"];
+
+/// Returns whether `#[entrance(deserialize)]` is given to the struct.
+fn extract_struct_attrs(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+    let mut deserialize = None;
+    let mut errors = Errors::default();
+    for (meta, attr) in extract_attributes(attrs, STRUCT_KEYS)? {
+        match attr {
+            Attribute::Deserialize => {
+                if deserialize.is_some() {
+                    errors.duplicated(&meta, "deserialize");
+                }
+                deserialize = Some(meta);
+            }
+            _ => unreachable!(),
+        }
+    }
+    errors.finish()?;
+    Ok(deserialize.is_some())
 }
 
 impl Parse for ArgumentsInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let lookahead = input.lookahead1();
-        if lookahead.peek(Token![struct]) {
-            let content;
-            let struct_token = input.parse()?;
-            let ident = input.parse()?;
-            let brace_token = braced!(content in input);
-            let fields = content.parse()?;
-            Ok(ArgumentsInput {
-                _struct_token: struct_token,
-                ident,
-                _brace_token: brace_token,
-                fields,
-            })
-        } else {
-            Err(lookahead.error())
-        }
+        let input: syn::DeriveInput = input.parse()?;
+        let mut errors = Errors::default();
+        let deserialize = errors.ok(extract_struct_attrs(&input.attrs));
+        let fields = match input.data {
+            syn::Data::Struct(syn::DataStruct {
+                fields: syn::Fields::Named(fields),
+                ..
+            }) => errors.ok(ArgumentFields::try_from(fields)),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "#[derive(Arguments)] supports only a struct with named fields",
+                ));
+            }
+        };
+        errors.finish()?;
+
+        Ok(ArgumentsInput {
+            ident: input.ident,
+            generics: input.generics,
+            fields: fields.unwrap(),
+            deserialize: deserialize.unwrap(),
+        })
     }
 }
 
 struct Field {
     ident: syn::Ident,
+    ty: syn::Type,
     description: String,
+    value_name: Option<String>,
+    min_values: usize,
+    max_values: Option<usize>,
+    value_names: Vec<String>,
+    hidden: bool,
+    deprecated: Option<String>,
+}
+
+impl Field {
+    /// Generates an expression parsing this field from the iterator `args`.
+    fn parse(&self, args: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let parse_next = quote! {
+            entrance::parse_argument(
+                (#args)
+                    .next()
+                    .ok_or(entrance::Error::InvalidNumberOfArguments)?
+            )?
+        };
+        match types::Arity::of(&self.ty) {
+            types::Arity::Single => parse_next,
+            types::Arity::Array(_) => quote! { entrance::parse_array(&mut #args)? },
+            types::Arity::Tuple(len) => {
+                let elements = (0..len).map(|_| &parse_next);
+                quote! { (#(#elements,)*) }
+            }
+        }
+    }
+
+    /// Generates an `entrance::Arg` describing this field of a struct with `generics`.
+    fn spec(&self, generics: &syn::Generics) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        let description = &self.description;
+        let value_name = match &self.value_name {
+            Some(name) => quote! { Some(#name) },
+            None => value_name_of(&self.ty, generics),
+        };
+        let hint = option_to_tokens(types::hint(&self.ty));
+        let num_values = match types::Arity::of(&self.ty) {
+            types::Arity::Single => quote! { 1 },
+            types::Arity::Array(len) => quote! { #len },
+            types::Arity::Tuple(len) => quote! { #len },
+        };
+        let value_names = &self.value_names;
+        let min_values = self.min_values;
+        let max_values = option_to_tokens(self.max_values);
+        let hidden = self.hidden;
+        let deprecated = option_to_tokens(self.deprecated.as_ref());
+        quote! {
+            entrance::Arg {
+                name: stringify!(#ident),
+                description: #description,
+                value_name: #value_name,
+                hint: #hint,
+                num_values: #num_values,
+                value_names: &[#(#value_names),*],
+                min_values: #min_values,
+                max_values: #max_values,
+                hidden: #hidden,
+                deprecated: #deprecated,
+            }
+        }
+    }
 }
 
 struct ArgumentFields {
     arguments: Vec<Field>,
     variable_argument: Option<Field>,
+    /// The number of `arguments` before `variable_argument`
+    variable_position: usize,
 }
 
-fn extract_arguments_attrs(
-    attrs: &[syn::Attribute],
-) -> (Option<(syn::Meta, String)>, Option<syn::Meta>) {
+const ARGUMENT_KEYS: &[&str] = &[
+    "description",
+    "variable_argument",
+    "value_name",
+    "min",
+    "max",
+    "value_names",
+    "hidden",
+    "deprecated",
+];
+
+struct ArgumentAttribute {
+    description: String,
+    variadic: Option<syn::Meta>,
+    value_name: Option<String>,
+    min: Option<(syn::Meta, usize)>,
+    max: Option<(syn::Meta, usize)>,
+    value_names: Option<(syn::Meta, Vec<String>)>,
+    hidden: bool,
+    deprecated: Option<String>,
+}
+
+fn extract_arguments_attrs(attrs: &[syn::Attribute]) -> syn::Result<ArgumentAttribute> {
     let mut description = None;
     let mut variadic = None;
+    let mut value_name = None;
+    let mut min = None;
+    let mut max = None;
+    let mut value_names = None;
+    let mut hidden = false;
+    let mut deprecated = None;
+    let mut errors = Errors::default();
 
-    let attrs = extract_attributes(attrs);
+    let attrs = extract_attributes(attrs, ARGUMENT_KEYS)?;
     for (meta, attr) in attrs {
         match attr {
             Attribute::Description(desc) => {
                 if description.is_some() {
-                    panic!("description attributes are duplicated");
+                    errors.duplicated(&meta, "description");
                 }
-                description = Some((meta, desc));
+                description = Some(desc);
             }
             Attribute::Variadic => {
+                if variadic.is_some() {
+                    errors.duplicated(&meta, "variable_argument");
+                }
                 variadic = Some(meta);
             }
-            _ => {
-                panic!("Invalid argument is given");
+            Attribute::ValueName(name) => {
+                if value_name.is_some() {
+                    errors.duplicated(&meta, "value_name");
+                }
+                value_name = Some(name);
+            }
+            Attribute::Min(count) => {
+                if min.is_some() {
+                    errors.duplicated(&meta, "min");
+                }
+                min = Some((meta, count));
             }
+            Attribute::Max(count) => {
+                if max.is_some() {
+                    errors.duplicated(&meta, "max");
+                }
+                max = Some((meta, count));
+            }
+            Attribute::ValueNames(names) => {
+                if value_names.is_some() {
+                    errors.duplicated(&meta, "value_names");
+                }
+                value_names = Some((meta, names));
+            }
+            Attribute::Hidden => {
+                if hidden {
+                    errors.duplicated(&meta, "hidden");
+                }
+                hidden = true;
+            }
+            Attribute::Deprecated(message) => {
+                if deprecated.is_some() {
+                    errors.duplicated(&meta, "deprecated");
+                }
+                deprecated = Some(message);
+            }
+            _ => unreachable!(),
         }
     }
 
-    (description, variadic)
-}
-
-impl Parse for ArgumentFields {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        let mut arguments = Vec::new();
-        let mut prev_variadic: Option<syn::Meta> = None;
-
-        let fields: Punctuated<_, Token![,]> = input.parse_terminated(syn::Field::parse_named)?;
-        for field in fields {
-            if let Some(meta) = prev_variadic {
-                return Err(syn::Error::new_spanned(
+    if variadic.is_none() {
+        for (key, bound) in [("min", &min), ("max", &max)].iter() {
+            if let Some((meta, _)) = bound {
+                errors.push(syn::Error::new_spanned(
                     meta,
-                    "The \"variable_argument\" attribute is allowed only for the last field",
+                    format!("`{}` is allowed only with `variable_argument`", key),
                 ));
             }
+        }
+    }
+    if let (Some((_, min)), Some((meta, max))) = (&min, &max) {
+        if min > max {
+            errors.push(syn::Error::new_spanned(
+                meta,
+                format!("`max` must not be less than `min` ({})", min),
+            ));
+        }
+    }
+    errors.finish()?;
 
-            let (description, variadic) = extract_arguments_attrs(&field.attrs);
-            if let Some(meta) = variadic {
-                prev_variadic = Some(meta);
-            }
+    Ok(ArgumentAttribute {
+        description: description.unwrap_or_else(String::new),
+        variadic,
+        value_name,
+        min,
+        max,
+        value_names,
+        hidden,
+        deprecated,
+    })
+}
 
-            let description = if let Some((_, description)) = description {
-                description
-            } else {
-                String::new()
+impl TryFrom<syn::FieldsNamed> for ArgumentFields {
+    type Error = syn::Error;
+
+    fn try_from(fields: syn::FieldsNamed) -> syn::Result<Self> {
+        let mut arguments = Vec::new();
+        let mut variable_argument = None;
+        let mut variable_position = 0;
+        let mut errors = Errors::default();
+
+        for field in fields.named {
+            let argument_attrs = match errors.ok(extract_arguments_attrs(&field.attrs)) {
+                Some(attrs) => attrs,
+                None => continue,
             };
 
-            arguments.push(Field {
+            if let Some((meta, names)) = &argument_attrs.value_names {
+                let expected = match types::Arity::of(&field.ty) {
+                    _ if argument_attrs.variadic.is_some() => None,
+                    types::Arity::Single => None,
+                    types::Arity::Array(syn::Expr::Lit(syn::ExprLit { lit, .. })) => lit.usize(),
+                    types::Arity::Array(_) => Some(names.len()),
+                    types::Arity::Tuple(len) => Some(len),
+                };
+                match expected {
+                    None => errors.push(syn::Error::new_spanned(
+                        meta,
+                        "`value_names` is allowed only for a field of an array or a tuple",
+                    )),
+                    Some(expected) if expected != names.len() => errors.push(
+                        syn::Error::new_spanned(meta, format!("expected {} value names", expected)),
+                    ),
+                    Some(_) => {}
+                }
+            }
+
+            let field_data = Field {
                 ident: field.ident.unwrap(),
-                description,
-            });
+                ty: field.ty,
+                description: argument_attrs.description,
+                value_name: argument_attrs.value_name,
+                min_values: argument_attrs.min.map_or(0, |(_, min)| min),
+                max_values: argument_attrs.max.map(|(_, max)| max),
+                value_names: argument_attrs
+                    .value_names
+                    .map(|(_, names)| names)
+                    .unwrap_or_default(),
+                hidden: argument_attrs.hidden,
+                deprecated: argument_attrs.deprecated,
+            };
+            match argument_attrs.variadic {
+                Some(meta) if variable_argument.is_some() => {
+                    errors.push(syn::Error::new_spanned(
+                        meta,
+                        "The \"variable_argument\" attribute is allowed only for one field",
+                    ));
+                }
+                Some(_) => {
+                    variable_argument = Some(field_data);
+                    variable_position = arguments.len();
+                }
+                None => arguments.push(field_data),
+            }
+        }
+        errors.finish()?;
+        if variable_argument.is_none() {
+            variable_position = arguments.len();
         }
-
-        let variable_argument = if prev_variadic.is_some() {
-            arguments.pop()
-        } else {
-            None
-        };
 
         Ok(Self {
             arguments,
             variable_argument,
+            variable_position,
         })
     }
 }
@@ -9,7 +9,7 @@ pub trait ExtMeta {
 
 impl ExtMeta for Meta {
     fn ident(&self) -> Option<&Ident> {
-        Some(self.get_path()?.get_ident()?)
+        self.get_path()?.get_ident()
     }
 
     fn get_path(&self) -> Option<&Path> {
@@ -37,6 +37,7 @@ impl ExtMeta for Meta {
 pub trait ExtLit {
     fn char(&self) -> Option<char>;
     fn str(&self) -> Option<String>;
+    fn usize(&self) -> Option<usize>;
 }
 
 impl ExtLit for Lit {
@@ -53,6 +54,13 @@ impl ExtLit for Lit {
             _ => None,
         }
     }
+
+    fn usize(&self) -> Option<usize> {
+        match self {
+            Self::Int(lit) => lit.base10_parse().ok(),
+            _ => None,
+        }
+    }
 }
 
 pub trait ExtNestedMeta {
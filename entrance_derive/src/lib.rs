@@ -4,6 +4,7 @@ extern crate proc_macro;
 mod arguments;
 mod extend_syn;
 mod options;
+mod types;
 
 use crate::extend_syn::*;
 use proc_macro::TokenStream;
@@ -23,55 +24,513 @@ pub fn options_derive(tokens: TokenStream) -> TokenStream {
 }
 
 enum Attribute {
-    Description(String),    // description
-    Variadic,               // variable_argument
-    Short(char),            // short
-    Informative(syn::Path), // informative
+    Description(String),         // description
+    Variadic,                    // variable_argument
+    Long(String),                // long
+    Short(char),                 // short
+    Informative(syn::Path),      // informative
+    AllowHyphenValues,           // allow_hyphen_values
+    Deserialize,                 // deserialize
+    ValueName(String),           // value_name
+    Hidden,                      // hidden
+    Deprecated(String),          // deprecated
+    Alias(String, AliasKind),    // alias, visible_alias, deprecated_alias
+    ShortAlias(char, AliasKind), // short_alias, visible_short_alias, deprecated_short_alias
+    Min(usize),                  // min
+    Max(usize),                  // max
+    ValueNames(Vec<String>),     // value_names
+    Delimiter(char),             // delimiter
+    Separator(char),             // separator
+    ImplicitValue(String),       // implicit_value
 }
 
-impl TryFrom<&syn::Meta> for Attribute {
-    type Error = ();
-    fn try_from(meta: &syn::Meta) -> Result<Self, Self::Error> {
-        match meta.path().get_ident().unwrap().to_string().as_str() {
+/// How an alias of an option is treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AliasKind {
+    /// Accepted silently
+    Hidden,
+    /// Shown in the help message
+    Visible,
+    /// Accepted with a warning
+    Deprecated,
+}
+
+impl AliasKind {
+    fn of(key: &str) -> Self {
+        if key.starts_with("visible_") {
+            AliasKind::Visible
+        } else if key.starts_with("deprecated_") {
+            AliasKind::Deprecated
+        } else {
+            AliasKind::Hidden
+        }
+    }
+}
+
+impl Attribute {
+    fn parse(meta: &syn::Meta, allowed: &[&str]) -> syn::Result<Self> {
+        let key = meta
+            .path()
+            .get_ident()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        if !allowed.contains(&key.as_str()) {
+            return Err(syn::Error::new_spanned(
+                meta.path(),
+                format!(
+                    "unknown attribute `{}`, expected one of: {}",
+                    quote::ToTokens::to_token_stream(meta.path()),
+                    expected_keys(allowed)
+                ),
+            ));
+        }
+
+        let invalid = |usage: &str| {
+            syn::Error::new_spanned(meta, format!("expected `#[entrance({})]`", usage))
+        };
+        match key.as_str() {
             "description" => {
-                let desc = meta.name_value().ok_or(())?.lit.str().ok_or(())?;
+                let desc = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.str())
+                    .ok_or_else(|| invalid("description = \"...\""))?;
                 Ok(Attribute::Description(desc))
             }
             "variable_argument" => {
-                meta.ident().ok_or(())?;
+                meta.ident().ok_or_else(|| invalid("variable_argument"))?;
                 Ok(Attribute::Variadic)
             }
+            "long" => {
+                let long = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.str())
+                    .ok_or_else(|| invalid("long = \"...\""))?;
+                Ok(Attribute::Long(long))
+            }
             "short" => {
-                let short = meta.name_value().ok_or(())?.lit.char().ok_or(())?;
+                let short = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.char())
+                    .ok_or_else(|| invalid("short = 'c'"))?;
                 Ok(Attribute::Short(short))
             }
+            "alias" | "visible_alias" | "deprecated_alias" => {
+                let alias = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.str())
+                    .ok_or_else(|| invalid(&format!("{} = \"...\"", key)))?;
+                Ok(Attribute::Alias(alias, AliasKind::of(&key)))
+            }
+            "short_alias" | "visible_short_alias" | "deprecated_short_alias" => {
+                let alias = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.char())
+                    .ok_or_else(|| invalid(&format!("{} = 'c'", key)))?;
+                Ok(Attribute::ShortAlias(alias, AliasKind::of(&key)))
+            }
+            "delimiter" => {
+                let delimiter = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.char())
+                    .ok_or_else(|| invalid("delimiter = 'c'"))?;
+                Ok(Attribute::Delimiter(delimiter))
+            }
+            "separator" => {
+                let separator = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.char())
+                    .ok_or_else(|| invalid("separator = 'c'"))?;
+                Ok(Attribute::Separator(separator))
+            }
+            "implicit_value" => {
+                let value = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.str())
+                    .ok_or_else(|| invalid("implicit_value = \"...\""))?;
+                Ok(Attribute::ImplicitValue(value))
+            }
+            "min" | "max" => {
+                let count = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.usize())
+                    .ok_or_else(|| invalid(&format!("{} = N", key)))?;
+                Ok(if key == "min" {
+                    Attribute::Min(count)
+                } else {
+                    Attribute::Max(count)
+                })
+            }
+            "value_names" => {
+                let names = match meta {
+                    syn::Meta::List(list) => list
+                        .nested
+                        .iter()
+                        .map(|nested| match nested {
+                            syn::NestedMeta::Lit(lit) => lit.str(),
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .filter(|names| !names.is_empty()),
+                    _ => None,
+                };
+                let names = names.ok_or_else(|| invalid("value_names(\"...\", ...)"))?;
+                Ok(Attribute::ValueNames(names))
+            }
+            "value_name" => {
+                let value_name = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.str())
+                    .filter(|value_name| !value_name.is_empty())
+                    .ok_or_else(|| invalid("value_name = \"...\""))?;
+                Ok(Attribute::ValueName(value_name))
+            }
+            "hidden" => {
+                meta.ident().ok_or_else(|| invalid("hidden"))?;
+                Ok(Attribute::Hidden)
+            }
+            "deprecated" => {
+                let message = meta
+                    .name_value()
+                    .and_then(|name_value| name_value.lit.str())
+                    .ok_or_else(|| invalid("deprecated = \"...\""))?;
+                Ok(Attribute::Deprecated(message))
+            }
+            "allow_hyphen_values" => {
+                meta.ident().ok_or_else(|| invalid("allow_hyphen_values"))?;
+                Ok(Attribute::AllowHyphenValues)
+            }
+            "deserialize" => {
+                meta.ident().ok_or_else(|| invalid("deserialize"))?;
+                Ok(Attribute::Deserialize)
+            }
             "informative" => {
-                let path = meta.single_list().ok_or(())?.clone();
+                let path = meta
+                    .single_list()
+                    .ok_or_else(|| invalid("informative(path::to::function)"))?
+                    .clone();
                 Ok(Attribute::Informative(path))
             }
-            _ => Err(()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Generates an expression of the `ValueName` of the values of `ty` as `Option<&str>`.
+///
+/// `None` is generated if `ty` uses the generic parameters, which cannot be used in the specs.
+fn value_name_of(ty: &syn::Type, generics: &syn::Generics) -> proc_macro2::TokenStream {
+    let ty = types::value_type(ty);
+    if types::uses_generics(ty, generics) {
+        return quote::quote! { None };
+    }
+    quote::quote! {
+        {
+            use entrance::NoValueName as _;
+            entrance::ValueNameOf::<#ty>::VALUE_NAME
         }
     }
 }
 
-fn extract_attributes(attrs: &[syn::Attribute]) -> Vec<(syn::Meta, Attribute)> {
-    attrs
+fn option_to_tokens<T: quote::ToTokens>(x: Option<T>) -> impl quote::ToTokens {
+    match x {
+        Some(c) => quote::quote! {
+            Some(#c)
+        },
+        None => quote::quote! {
+            None
+        },
+    }
+}
+
+fn expected_keys(allowed: &[&str]) -> String {
+    allowed
         .iter()
-        .filter_map(|attr| attr.parse_meta().ok())
-        .filter_map(|meta| {
-            if let syn::Meta::List(list) = meta {
-                Some(list.nested)
-            } else {
+        .map(|key| format!("`{}`", key))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parses all `#[entrance(...)]` attributes whose keys are contained in `allowed`.
+///
+/// Errors for every malformed attribute are combined into one.
+fn extract_attributes(
+    attrs: &[syn::Attribute],
+    allowed: &[&str],
+) -> syn::Result<Vec<(syn::Meta, Attribute)>> {
+    let mut errors = Errors::default();
+    let mut attributes = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("entrance")) {
+        let list = match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => list,
+            Ok(meta) => {
+                errors.push(syn::Error::new_spanned(
+                    meta,
+                    format!(
+                        "expected `#[entrance(...)]` with one of: {}",
+                        expected_keys(allowed)
+                    ),
+                ));
+                continue;
+            }
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+
+        for nested in list.nested {
+            match nested {
+                syn::NestedMeta::Meta(meta) => {
+                    if let Some(attr) = errors.ok(Attribute::parse(&meta, allowed)) {
+                        attributes.push((meta, attr));
+                    }
+                }
+                syn::NestedMeta::Lit(lit) => errors.push(syn::Error::new_spanned(
+                    lit,
+                    format!("expected one of: {}", expected_keys(allowed)),
+                )),
+            }
+        }
+    }
+
+    errors.finish()?;
+    Ok(attributes)
+}
+
+/// Accumulates errors to report several of them at once.
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+
+impl Errors {
+    fn push(&mut self, err: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(err),
+            None => self.0 = Some(err),
+        }
+    }
+
+    /// Returns the value of `result`, or records its error.
+    fn ok<T>(&mut self, result: syn::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.push(err);
                 None
             }
-        })
-        .flatten()
-        .map(|nested| {
-            if let syn::NestedMeta::Meta(meta) = nested {
-                let attr = Attribute::try_from(&meta).unwrap();
-                return (meta, attr);
+        }
+    }
+
+    fn duplicated(&mut self, meta: &syn::Meta, key: &str) {
+        self.push(syn::Error::new_spanned(
+            meta,
+            format!("`{}` attribute is duplicated", key),
+        ));
+    }
+
+    fn finish(self) -> syn::Result<()> {
+        match self.0 {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    fn errors<T: syn::parse::Parse>(input: &str) -> Vec<String> {
+        match syn::parse_str::<T>(input) {
+            Ok(_) => Vec::new(),
+            Err(err) => err.into_iter().map(|err| err.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn unknown_attribute() {
+        let errors = errors::<crate::options::OptionsInput>(
+            "enum Opts { #[entrance(shrot = 'v')] Verbose }",
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "unknown attribute `shrot`, expected one of: `description`, `long`, `short`, `informative`, `allow_hyphen_values`, `value_name`, `hidden`, `deprecated`, `alias`, `visible_alias`, `deprecated_alias`, `short_alias`, `visible_short_alias`, `deprecated_short_alias`, `delimiter`, `min`, `max`, `separator`, `implicit_value`"
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_errors() {
+        let errors = errors::<crate::arguments::ArgumentsInput>(
+            r#"
+            struct Args {
+                #[entrance(description = "a", description = "b")]
+                a: i32,
+                #[entrance(short = 'b')]
+                b: i32,
+                #[allow(dead_code)]
+                #[entrance(variable_argument = true)]
+                c: Vec<i32>,
             }
-            panic!("Invalid attribute is given: not syn::NestedMeta::Meta");
-        })
-        .collect()
+            "#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "`description` attribute is duplicated",
+                "unknown attribute `short`, expected one of: `description`, `variable_argument`, `value_name`, `min`, `max`, `value_names`, `hidden`, `deprecated`",
+                "expected `#[entrance(variable_argument)]`",
+            ]
+        );
+    }
+
+    #[test]
+    fn variable_argument_bounds() {
+        let invalid = errors::<crate::arguments::ArgumentsInput>(
+            r#"
+            struct Args {
+                #[entrance(min = 1)]
+                a: i32,
+                #[entrance(variable_argument, min = 3, max = 2)]
+                b: Vec<i32>,
+            }
+            "#,
+        );
+        assert_eq!(
+            invalid,
+            vec![
+                "`min` is allowed only with `variable_argument`",
+                "`max` must not be less than `min` (3)",
+            ]
+        );
+
+        let duplicated = errors::<crate::arguments::ArgumentsInput>(
+            r#"
+            struct Args {
+                #[entrance(variable_argument)]
+                a: Vec<i32>,
+                #[entrance(variable_argument)]
+                b: Vec<i32>,
+            }
+            "#,
+        );
+        assert_eq!(
+            duplicated,
+            vec!["The \"variable_argument\" attribute is allowed only for one field"]
+        );
+    }
+
+    #[test]
+    fn value_names() {
+        let errors = errors::<crate::arguments::ArgumentsInput>(
+            r#"
+            struct Args {
+                #[entrance(value_names("x", "y"))]
+                a: i32,
+                #[entrance(value_names("x", "y"))]
+                b: [i32; 3],
+                #[entrance(value_names())]
+                c: (i32, i32),
+            }
+            "#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "`value_names` is allowed only for a field of an array or a tuple",
+                "expected 3 value names",
+                "expected `#[entrance(value_names(\"...\", ...))]`",
+            ]
+        );
+    }
+
+    #[test]
+    fn option_occurrences() {
+        let errors = errors::<crate::options::OptionsInput>(
+            r#"
+            enum Opts {
+                #[entrance(delimiter = ',')]
+                Verbose,
+                #[entrance(delimiter = ',')]
+                Jobs(u32),
+                #[entrance(min = 2, max = 1)]
+                Include(Vec<String>),
+                #[entrance(separator = ':')]
+                Tag(Vec<String>),
+                #[entrance(implicit_value = "auto")]
+                Color(String),
+            }
+            "#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "`delimiter` is allowed only for a variant with a field",
+                "`delimiter` is allowed only for a variant with a collection like `Vec<T>`",
+                "`max` must not be less than `min` (2)",
+                "`separator` is allowed only for a variant with a map like `HashMap<K, V>`",
+                "`implicit_value` is allowed only for a variant with an `Option<T>`",
+            ]
+        );
+    }
+
+    #[test]
+    fn conflicting_options() {
+        let invalid = errors::<crate::options::OptionsInput>(
+            r#"
+            enum Opts {
+                #[entrance(short = '-')]
+                Dash,
+                #[entrance(long = "dry run")]
+                DryRun,
+            }
+            "#,
+        );
+        assert_eq!(
+            invalid,
+            vec![
+                "invalid short option '-': `-` is reserved for the option prefix",
+                "invalid long option `dry run`: it must not contain whitespace or control characters",
+            ]
+        );
+
+        let conflicts = errors::<crate::options::OptionsInput>(
+            r#"
+            enum Opts {
+                #[entrance(short = 'v')]
+                Version,
+                #[entrance(long = "version", short = 'v')]
+                Verbose,
+            }
+            "#,
+        );
+        assert_eq!(
+            conflicts,
+            vec![
+                "duplicate option `--version`",
+                "option `--version` is first declared here",
+                "duplicate option `-v`",
+                "option `-v` is first declared here",
+            ]
+        );
+
+        let alias_conflicts = errors::<crate::options::OptionsInput>(
+            r#"
+            enum Opts {
+                #[entrance(alias = "colour", short_alias = 'c')]
+                Color,
+                #[entrance(visible_alias = "colour", short = 'c')]
+                Colors,
+            }
+            "#,
+        );
+        assert_eq!(
+            alias_conflicts,
+            vec![
+                "duplicate option `--colour`",
+                "option `--colour` is first declared here",
+                "duplicate option `-c`",
+                "option `-c` is first declared here",
+            ]
+        );
+    }
 }
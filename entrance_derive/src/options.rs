@@ -1,33 +1,39 @@
 use crate::*;
 use proc_macro::TokenStream;
+use proc_macro2::Span;
 use quote::quote;
+use std::collections::HashMap;
 use syn::parse::{Parse, ParseStream};
-use syn::punctuated::Punctuated;
-use syn::{braced, Token};
+use syn::spanned::Spanned;
 
 pub struct OptionsInput {
-    _struct_token: Token![enum],
     ident: syn::Ident,
-    _brace_token: syn::token::Brace,
-    variants: Punctuated<OptionVariant, Token![,]>,
+    generics: syn::Generics,
+    variants: Vec<OptionVariant>,
 }
 
 impl OptionsInput {
     pub fn gen(&self) -> TokenStream {
         let ident = &self.ident;
         let options: Vec<_> = self.variants.iter().collect();
-        let long_option_arms = options.iter().map(|option| {
-            let option = &option.ident;
-            let long = get_long_option(option);
+        let long_option_arms = options.iter().enumerate().map(|(index, option)| {
+            let long = &option.long;
+            let aliases = option.aliases.iter().map(|alias| &alias.name);
+            let construct = option.construct(ident, index);
             quote! {
-                #long => Ok(#ident::#option),
+                #long #(| #aliases)* => Ok(#construct),
             }
         });
-        let short_option_arms = options.iter().filter_map(|option| {
-            let short = option.short?;
-            let option = &option.ident;
+        let short_option_arms = options.iter().enumerate().filter_map(|(index, option)| {
+            let mut shorts = option
+                .short
+                .into_iter()
+                .chain(option.short_aliases.iter().map(|alias| alias.name))
+                .peekable();
+            shorts.peek()?;
+            let construct = option.construct(ident, index);
             Some(quote! {
-                #short => Ok(#ident::#option),
+                #(#shorts)|* => Ok(#construct),
             })
         });
         let parse_lines = quote! {
@@ -59,7 +65,7 @@ impl OptionsInput {
             let is_informative = option.informative.is_some();
             let option = &option.ident;
             quote! {
-                Self::#option => #is_informative
+                Self::#option { .. } => #is_informative
             }
         });
 
@@ -67,22 +73,113 @@ impl OptionsInput {
             let option = &opt.ident;
             if let Some(path) = &opt.informative {
                 quote! {
-                    Self::#option => #path(command),
+                    Self::#option { .. } => #path(command),
                 }
             } else {
                 quote! {
-                    Self::#option => {}
+                    Self::#option { .. } => {}
                 }
             }
         });
 
-        let idents = options.iter().map(|option| get_long_option(&option.ident));
+        let longs = options.iter().map(|option| &option.long);
         let num_options = options.len();
         let descriptions = options.iter().map(|option| &option.description);
         let shorts = options.iter().map(|option| option_to_tokens(option.short));
+        let allow_hyphen_values = options.iter().map(|option| option.allow_hyphen_values);
+        let value_names = options
+            .iter()
+            .map(|option| match (&option.value_name, &option.value) {
+                (Some(name), _) => quote! { Some(#name) },
+                (None, Some(ty)) => {
+                    let value_name = value_name_of(ty, &self.generics);
+                    let hint = option_to_tokens(types::hint(ty));
+                    quote! {
+                        match #value_name {
+                            Some(name) => Some(name),
+                            None => #hint,
+                        }
+                    }
+                }
+                (None, None) => quote! { None },
+            });
+        let hidden = options.iter().map(|option| option.hidden);
+        let alias_lists = |kind: AliasKind| {
+            options.iter().map(move |option| {
+                let aliases = option
+                    .aliases
+                    .iter()
+                    .filter(move |alias| alias.kind == kind)
+                    .map(|alias| &alias.name);
+                quote! { &[#(#aliases),*] }
+            })
+        };
+        let short_alias_lists = |kind: AliasKind| {
+            options.iter().map(move |option| {
+                let aliases = option
+                    .short_aliases
+                    .iter()
+                    .filter(move |alias| alias.kind == kind)
+                    .map(|alias| alias.name);
+                quote! { &[#(#aliases),*] }
+            })
+        };
+        let aliases = alias_lists(AliasKind::Hidden);
+        let visible_aliases = alias_lists(AliasKind::Visible);
+        let deprecated_aliases = alias_lists(AliasKind::Deprecated);
+        let short_aliases = short_alias_lists(AliasKind::Hidden);
+        let visible_short_aliases = short_alias_lists(AliasKind::Visible);
+        let deprecated_short_aliases = short_alias_lists(AliasKind::Deprecated);
+        let deprecated = options
+            .iter()
+            .map(|option| option_to_tokens(option.deprecated.as_ref()));
+        let delimiters = options
+            .iter()
+            .map(|option| option_to_tokens(option.delimiter));
+        let min_occurrences = options.iter().map(|option| option.min_occurrences);
+        let max_occurrences = options
+            .iter()
+            .map(|option| option_to_tokens(option.max_occurrences));
+        let separators = options
+            .iter()
+            .map(|option| option_to_tokens(option.separator));
+        let implicit_values = options
+            .iter()
+            .map(|option| option_to_tokens(option.implicit_value.as_ref()));
+        let collections: Vec<_> = options
+            .iter()
+            .filter(|option| option.is_collection())
+            .map(|option| &option.ident)
+            .collect();
+        let merge = if collections.is_empty() {
+            quote! {}
+        } else {
+            quote! {
+                fn merge(&mut self, other: Self) -> std::result::Result<(), Self> {
+                    match (self, other) {
+                        #(
+                            (Self::#collections(values), Self::#collections(other)) => {
+                                values.extend(other);
+                                Ok(())
+                            }
+                        )*
+                        (_, other) => Err(other),
+                    }
+                }
+            }
+        };
+        let values = options.iter().map(|option| match &option.value {
+            Some(ty) if types::is_option(ty) => quote! { entrance::ValueKind::Optional },
+            Some(_) => quote! { entrance::ValueKind::Required },
+            None => quote! { entrance::ValueKind::Flag },
+        });
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
         (quote! {
-            impl entrance::Options for #ident {
-                fn parse(option: entrance::OptionItem) -> entrance::Result<Self> {
+            impl #impl_generics entrance::Options for #ident #ty_generics #where_clause {
+                fn parse(
+                    option: entrance::OptionItem,
+                    cursor: &mut entrance::ArgCursor,
+                ) -> entrance::Result<Self> {
                     #parse_lines
                 }
 
@@ -105,18 +202,36 @@ impl OptionsInput {
                     }
                 }
 
-                fn spec() -> &'static [entrance::Opt] {
-                    static OPTS: [entrance::Opt; #num_options] = [
+                fn spec() -> &'static [entrance::Opt<'static>] {
+                    static OPTS: [entrance::Opt<'static>; #num_options] = [
                         #(
                             entrance::Opt {
-                                long: #idents,
+                                long: #longs,
                                 short: #shorts,
                                 description: #descriptions,
+                                value: #values,
+                                allow_hyphen_values: #allow_hyphen_values,
+                                value_name: #value_names,
+                                hidden: #hidden,
+                                deprecated: #deprecated,
+                                aliases: #aliases,
+                                visible_aliases: #visible_aliases,
+                                short_aliases: #short_aliases,
+                                visible_short_aliases: #visible_short_aliases,
+                                deprecated_aliases: #deprecated_aliases,
+                                deprecated_short_aliases: #deprecated_short_aliases,
+                                delimiter: #delimiters,
+                                min_occurrences: #min_occurrences,
+                                max_occurrences: #max_occurrences,
+                                separator: #separators,
+                                implicit_value: #implicit_values,
                             },
                         )*
                     ];
                     &OPTS
                 }
+
+                #merge
             }
         })
         .into()
@@ -125,103 +240,505 @@ impl OptionsInput {
 
 impl Parse for OptionsInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let lookahead = input.lookahead1();
-        if lookahead.peek(Token![enum]) {
-            let content;
-            let struct_token = input.parse()?;
-            let ident = input.parse()?;
-            let brace_token = braced!(content in input);
-            let variants = content.parse_terminated(OptionVariant::parse)?;
-            Ok(Self {
-                _struct_token: struct_token,
-                ident,
-                _brace_token: brace_token,
-                variants,
-            })
-        } else {
-            Err(lookahead.error())
-        }
+        let input: syn::DeriveInput = input.parse()?;
+        let variants = match input.data {
+            syn::Data::Enum(data) => {
+                let mut variants = Vec::new();
+                let mut errors = Errors::default();
+                for variant in data.variants {
+                    variants.extend(errors.ok(OptionVariant::try_from(variant)));
+                }
+                errors.finish()?;
+                check_conflicts(&variants)?;
+                variants
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "#[derive(Options)] supports only an enum",
+                ));
+            }
+        };
+
+        Ok(Self {
+            ident: input.ident,
+            generics: input.generics,
+            variants,
+        })
     }
 }
 
 struct OptionAttribute {
-    short: Option<char>,
+    long: Option<(String, Span)>,
+    short: Option<(char, Span)>,
     description: String,
     informative: Option<syn::Path>,
+    allow_hyphen_values: Option<syn::Meta>,
+    value_name: Option<(syn::Meta, String)>,
+    hidden: bool,
+    deprecated: Option<String>,
+    aliases: Vec<Alias<String>>,
+    short_aliases: Vec<Alias<char>>,
+    delimiter: Option<(syn::Meta, char)>,
+    min: Option<usize>,
+    max: Option<usize>,
+    separator: Option<(syn::Meta, char)>,
+    implicit_value: Option<(syn::Meta, String)>,
+}
+
+/// An alias of an option with its span and how it is treated.
+struct Alias<T> {
+    name: T,
+    span: Span,
+    kind: AliasKind,
 }
 
-fn extract_options_attrs(attrs: &[syn::Attribute]) -> OptionAttribute {
+const OPTION_KEYS: &[&str] = &[
+    "description",
+    "long",
+    "short",
+    "informative",
+    "allow_hyphen_values",
+    "value_name",
+    "hidden",
+    "deprecated",
+    "alias",
+    "visible_alias",
+    "deprecated_alias",
+    "short_alias",
+    "visible_short_alias",
+    "deprecated_short_alias",
+    "delimiter",
+    "min",
+    "max",
+    "separator",
+    "implicit_value",
+];
+
+fn extract_options_attrs(attrs: &[syn::Attribute]) -> syn::Result<OptionAttribute> {
+    let mut long = None;
     let mut short = None;
     let mut description = None;
     let mut informative = None;
+    let mut allow_hyphen_values = None;
+    let mut value_name = None;
+    let mut hidden = false;
+    let mut deprecated = None;
+    let mut aliases = Vec::new();
+    let mut short_aliases = Vec::new();
+    let mut delimiter = None;
+    let mut min = None;
+    let mut max = None;
+    let mut separator = None;
+    let mut implicit_value = None;
+    let mut errors = Errors::default();
 
-    let attrs = extract_attributes(attrs);
-    for (_meta, attr) in attrs {
+    let attrs = extract_attributes(attrs, OPTION_KEYS)?;
+    for (meta, attr) in attrs {
         match attr {
             Attribute::Description(desc) => {
                 if description.is_some() {
-                    panic!("description attributes are duplicated");
+                    errors.duplicated(&meta, "description");
                 }
                 description = Some(desc);
             }
+            Attribute::Long(name) => {
+                if long.is_some() {
+                    errors.duplicated(&meta, "long");
+                }
+                if let Err(reason) = validate_long(&name) {
+                    errors.push(syn::Error::new_spanned(
+                        &meta,
+                        format!("invalid long option `{}`: {}", name, reason),
+                    ));
+                }
+                long = Some((name, meta.span()));
+            }
             Attribute::Short(c) => {
                 if short.is_some() {
-                    panic!("short attributes are duplicated");
+                    errors.duplicated(&meta, "short");
+                }
+                if let Err(reason) = validate_short(c) {
+                    errors.push(syn::Error::new_spanned(
+                        &meta,
+                        format!("invalid short option {:?}: {}", c, reason),
+                    ));
                 }
-                short = Some(c);
+                short = Some((c, meta.span()));
             }
             Attribute::Informative(path) => {
                 if informative.is_some() {
-                    panic!("informative attributes are duplicated");
+                    errors.duplicated(&meta, "informative");
                 }
                 informative = Some(path);
             }
-            _ => {
-                panic!("Invalid argument is given");
+            Attribute::AllowHyphenValues => {
+                if allow_hyphen_values.is_some() {
+                    errors.duplicated(&meta, "allow_hyphen_values");
+                }
+                allow_hyphen_values = Some(meta);
+            }
+            Attribute::ValueName(name) => {
+                if value_name.is_some() {
+                    errors.duplicated(&meta, "value_name");
+                }
+                value_name = Some((meta, name));
+            }
+            Attribute::Hidden => {
+                if hidden {
+                    errors.duplicated(&meta, "hidden");
+                }
+                hidden = true;
+            }
+            Attribute::Deprecated(message) => {
+                if deprecated.is_some() {
+                    errors.duplicated(&meta, "deprecated");
+                }
+                deprecated = Some(message);
+            }
+            Attribute::Alias(name, kind) => {
+                if let Err(reason) = validate_long(&name) {
+                    errors.push(syn::Error::new_spanned(
+                        &meta,
+                        format!("invalid alias `{}`: {}", name, reason),
+                    ));
+                }
+                aliases.push(Alias {
+                    name,
+                    span: meta.span(),
+                    kind,
+                });
+            }
+            Attribute::ShortAlias(c, kind) => {
+                if let Err(reason) = validate_short(c) {
+                    errors.push(syn::Error::new_spanned(
+                        &meta,
+                        format!("invalid short alias {:?}: {}", c, reason),
+                    ));
+                }
+                short_aliases.push(Alias {
+                    name: c,
+                    span: meta.span(),
+                    kind,
+                });
             }
+            Attribute::Delimiter(c) => {
+                if delimiter.is_some() {
+                    errors.duplicated(&meta, "delimiter");
+                }
+                delimiter = Some((meta, c));
+            }
+            Attribute::Min(count) => {
+                if min.is_some() {
+                    errors.duplicated(&meta, "min");
+                }
+                min = Some(count);
+            }
+            Attribute::Max(count) => {
+                if max.is_some() {
+                    errors.duplicated(&meta, "max");
+                }
+                max = Some((meta, count));
+            }
+            Attribute::Separator(c) => {
+                if separator.is_some() {
+                    errors.duplicated(&meta, "separator");
+                }
+                separator = Some((meta, c));
+            }
+            Attribute::ImplicitValue(value) => {
+                if implicit_value.is_some() {
+                    errors.duplicated(&meta, "implicit_value");
+                }
+                implicit_value = Some((meta, value));
+            }
+            _ => unreachable!(),
         }
     }
+    if let (Some(min), Some((meta, max))) = (min, &max) {
+        if min > *max {
+            errors.push(syn::Error::new_spanned(
+                meta,
+                format!("`max` must not be less than `min` ({})", min),
+            ));
+        }
+    }
+    errors.finish()?;
 
-    OptionAttribute {
+    Ok(OptionAttribute {
+        long,
         short,
         description: description.unwrap_or_else(String::new),
         informative,
-    }
+        allow_hyphen_values,
+        value_name,
+        hidden,
+        deprecated,
+        aliases,
+        short_aliases,
+        delimiter,
+        min,
+        max: max.map(|(_, max)| max),
+        separator,
+        implicit_value,
+    })
 }
 
 struct OptionVariant {
     ident: syn::Ident,
+    value: Option<syn::Type>,
+    long: String,
+    long_span: Span,
     short: Option<char>,
+    short_span: Span,
     description: String,
     informative: Option<syn::Path>,
+    allow_hyphen_values: bool,
+    /// The value name given or derived from the separator, preceding the one of the type
+    value_name: Option<String>,
+    hidden: bool,
+    deprecated: Option<String>,
+    aliases: Vec<Alias<String>>,
+    short_aliases: Vec<Alias<char>>,
+    delimiter: Option<char>,
+    min_occurrences: usize,
+    max_occurrences: Option<usize>,
+    separator: Option<char>,
+    implicit_value: Option<String>,
 }
-impl Parse for OptionVariant {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        let variant = syn::Variant::parse(input)?;
+
+impl TryFrom<syn::Variant> for OptionVariant {
+    type Error = syn::Error;
+
+    fn try_from(variant: syn::Variant) -> syn::Result<Self> {
+        let mut errors = Errors::default();
+        let value = match variant.fields {
+            syn::Fields::Unit => None,
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                fields.unnamed.into_iter().next().map(|field| field.ty)
+            }
+            fields => {
+                errors.push(syn::Error::new_spanned(
+                    fields,
+                    "#[derive(Options)] supports only variants without any field \
+                     or with a single unnamed field",
+                ));
+                None
+            }
+        };
 
         let ident = variant.ident;
-        let option_attrs = extract_options_attrs(&variant.attrs);
+        let option_attrs = errors.ok(extract_options_attrs(&variant.attrs));
+        if let (Some(attrs), None) = (&option_attrs, &value) {
+            let value_attrs = [
+                ("allow_hyphen_values", attrs.allow_hyphen_values.as_ref()),
+                (
+                    "value_name",
+                    attrs.value_name.as_ref().map(|(meta, _)| meta),
+                ),
+            ];
+            for (key, meta) in value_attrs.iter() {
+                if let Some(meta) = meta {
+                    errors.push(syn::Error::new_spanned(
+                        meta,
+                        format!("`{}` is allowed only for a variant with a field", key),
+                    ));
+                }
+            }
+        }
+        if let Some(attrs) = &option_attrs {
+            match (&attrs.delimiter, &value) {
+                (Some((meta, _)), Some(ty)) if !types::is_collection(ty) && !types::is_map(ty) => {
+                    errors.push(syn::Error::new_spanned(
+                        meta,
+                        "`delimiter` is allowed only for a variant with a collection like `Vec<T>`",
+                    ));
+                }
+                (Some((meta, _)), None) => {
+                    errors.push(syn::Error::new_spanned(
+                        meta,
+                        "`delimiter` is allowed only for a variant with a field",
+                    ));
+                }
+                _ => {}
+            }
+            if let Some((meta, _)) = &attrs.separator {
+                if !matches!(&value, Some(ty) if types::is_key_value(ty)) {
+                    errors.push(syn::Error::new_spanned(
+                        meta,
+                        "`separator` is allowed only for a variant with a map like `HashMap<K, V>`",
+                    ));
+                }
+            }
+            if let Some((meta, _)) = &attrs.implicit_value {
+                if !matches!(&value, Some(ty) if types::is_option(ty)) {
+                    errors.push(syn::Error::new_spanned(
+                        meta,
+                        "`implicit_value` is allowed only for a variant with an `Option<T>`",
+                    ));
+                }
+            }
+        }
+        errors.finish()?;
+        let option_attrs = option_attrs.unwrap();
+
+        let (long, long_span) = option_attrs
+            .long
+            .unwrap_or_else(|| (ident.to_string().to_lowercase(), ident.span()));
+        let (short, short_span) = match option_attrs.short {
+            Some((short, span)) => (Some(short), span),
+            None => (None, ident.span()),
+        };
+
+        let separator = match &value {
+            Some(ty) if types::is_key_value(ty) => Some(
+                option_attrs
+                    .separator
+                    .map_or('=', |(_, separator)| separator),
+            ),
+            _ => None,
+        };
+        let value_name = match (option_attrs.value_name, separator) {
+            (Some((_, name)), _) => Some(name),
+            (None, Some(separator)) => Some(format!("KEY{}VALUE", separator)),
+            (None, None) => None,
+        };
 
         Ok(Self {
             ident,
-            short: option_attrs.short,
+            value,
+            long,
+            long_span,
+            short,
+            short_span,
             description: option_attrs.description,
             informative: option_attrs.informative,
+            allow_hyphen_values: option_attrs.allow_hyphen_values.is_some(),
+            value_name,
+            hidden: option_attrs.hidden,
+            deprecated: option_attrs.deprecated,
+            aliases: option_attrs.aliases,
+            short_aliases: option_attrs.short_aliases,
+            delimiter: option_attrs.delimiter.map(|(_, delimiter)| delimiter),
+            min_occurrences: option_attrs.min.unwrap_or(0),
+            max_occurrences: option_attrs.max,
+            separator,
+            implicit_value: option_attrs.implicit_value.map(|(_, value)| value),
         })
     }
 }
 
-fn get_long_option(ident: &syn::Ident) -> String {
-    ident.to_string().to_lowercase()
+impl OptionVariant {
+    /// Generates an expression to construct this variant, taking its value from `cursor`.
+    ///
+    /// `index` is the position of this variant in `Options::spec()`.
+    fn construct(&self, ident: &syn::Ident, index: usize) -> proc_macro2::TokenStream {
+        let option = &self.ident;
+        if self.separator.is_some() {
+            quote! {
+                #ident::#option({
+                    let spec = &<Self as entrance::Options>::spec()[#index];
+                    entrance::parse_key_values(spec, spec.take_values(cursor)?.into_iter())?
+                })
+            }
+        } else if matches!(&self.value, Some(ty) if types::is_option(ty)) {
+            quote! {
+                #ident::#option(
+                    <Self as entrance::Options>::spec()[#index]
+                        .take_optional_value(cursor)
+                        .map(entrance::parse_argument)
+                        .transpose()?
+                )
+            }
+        } else if self.is_collection() {
+            quote! {
+                #ident::#option(entrance::parse_variable_argument(
+                    <Self as entrance::Options>::spec()[#index].take_values(cursor)?.into_iter()
+                )?)
+            }
+        } else if self.value.is_some() {
+            quote! {
+                #ident::#option(entrance::parse_argument(
+                    <Self as entrance::Options>::spec()[#index].take_value(cursor)?
+                )?)
+            }
+        } else {
+            quote! { #ident::#option }
+        }
+    }
+
+    /// Returns `true` if the values of all the occurrences are collected into the field.
+    fn is_collection(&self) -> bool {
+        matches!(&self.value, Some(ty) if types::is_collection(ty) || types::is_map(ty))
+    }
+}
+
+fn validate_long(long: &str) -> Result<(), &'static str> {
+    if long.is_empty() {
+        Err("it must not be empty")
+    } else if long.starts_with('-') {
+        Err("it must not start with `-`")
+    } else if long.contains('=') {
+        Err("it must not contain `=`")
+    } else if long.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        Err("it must not contain whitespace or control characters")
+    } else {
+        Ok(())
+    }
 }
 
-fn option_to_tokens<T: quote::ToTokens>(x: Option<T>) -> impl quote::ToTokens {
-    match x {
-        Some(c) => quote! {
-            Some(#c)
-        },
-        None => quote! {
-            None
-        },
+fn validate_short(short: char) -> Result<(), &'static str> {
+    if short == '-' {
+        Err("`-` is reserved for the option prefix")
+    } else if short.is_whitespace() || short.is_control() {
+        Err("whitespace and control characters are not allowed")
+    } else {
+        Ok(())
+    }
+}
+
+/// Rejects options sharing the same long name or short character, including aliases.
+fn check_conflicts(variants: &[OptionVariant]) -> syn::Result<()> {
+    let mut errors = Errors::default();
+    let mut longs = HashMap::new();
+    let mut shorts = HashMap::new();
+
+    let mut conflict = |span: Span, first: Span, name: String| {
+        errors.push(syn::Error::new(
+            span,
+            format!("duplicate option `{}`", name),
+        ));
+        errors.push(syn::Error::new(
+            first,
+            format!("option `{}` is first declared here", name),
+        ));
+    };
+
+    for variant in variants {
+        let aliases = variant
+            .aliases
+            .iter()
+            .map(|alias| (&alias.name, alias.span));
+        for (long, span) in std::iter::once((&variant.long, variant.long_span)).chain(aliases) {
+            if let Some(&first) = longs.get(long) {
+                conflict(span, first, format!("--{}", long));
+            } else {
+                longs.insert(long, span);
+            }
+        }
+        let short_aliases = variant
+            .short_aliases
+            .iter()
+            .map(|alias| (alias.name, alias.span));
+        let short = variant.short.map(|short| (short, variant.short_span));
+        for (short, span) in short.into_iter().chain(short_aliases) {
+            if let Some(&first) = shorts.get(&short) {
+                conflict(span, first, format!("-{}", short));
+            } else {
+                shorts.insert(short, span);
+            }
+        }
     }
+
+    errors.finish()
 }
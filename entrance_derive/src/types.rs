@@ -0,0 +1,197 @@
+/// Returns the last segment of the path of `ty`, e.g. `Vec<T>` for `std::vec::Vec<T>`.
+pub fn last_segment(ty: &syn::Type) -> Option<&syn::PathSegment> {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+        _ => None,
+    }
+}
+
+/// Returns the element type of `ty`, e.g. `T` for `Vec<T>`, `[T; N]` and `(T, U)`.
+///
+/// The first generic type argument is returned for other paths.
+pub fn element_type(ty: &syn::Type) -> Option<&syn::Type> {
+    match ty {
+        syn::Type::Array(array) => return Some(&array.elem),
+        syn::Type::Tuple(tuple) => return tuple.elems.first(),
+        _ => {}
+    }
+    match &last_segment(ty)?.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// Returns `true` if `ty` is a collection of the standard library, e.g. `Vec<T>`.
+pub fn is_collection(ty: &syn::Type) -> bool {
+    match last_segment(ty) {
+        Some(segment) => [
+            "Vec",
+            "VecDeque",
+            "LinkedList",
+            "HashSet",
+            "BTreeSet",
+            "BinaryHeap",
+        ]
+        .iter()
+        .any(|name| segment.ident == name),
+        None => false,
+    }
+}
+
+/// Returns `true` if `ty` is `Option<T>`.
+pub fn is_option(ty: &syn::Type) -> bool {
+    matches!(last_segment(ty), Some(segment) if segment.ident == "Option")
+}
+
+/// Returns `true` if `ty` is a map of the standard library, e.g. `HashMap<K, V>`.
+pub fn is_map(ty: &syn::Type) -> bool {
+    match last_segment(ty) {
+        Some(segment) => segment.ident == "HashMap" || segment.ident == "BTreeMap",
+        None => false,
+    }
+}
+
+/// Returns `true` if `ty` holds pairs of a key and a value, e.g. `HashMap<K, V>` or `Vec<(K, V)>`.
+pub fn is_key_value(ty: &syn::Type) -> bool {
+    is_map(ty)
+        || is_collection(ty)
+            && matches!(element_type(ty), Some(syn::Type::Tuple(tuple)) if tuple.elems.len() == 2)
+}
+
+/// The number of positional arguments taken by a field.
+pub enum Arity<'a> {
+    Single,
+    /// An array with the length
+    Array(&'a syn::Expr),
+    /// A tuple with the number of the elements
+    Tuple(usize),
+}
+
+impl<'a> Arity<'a> {
+    pub fn of(ty: &'a syn::Type) -> Self {
+        match ty {
+            syn::Type::Array(array) => Arity::Array(&array.len),
+            syn::Type::Tuple(tuple) => Arity::Tuple(tuple.elems.len()),
+            _ => Arity::Single,
+        }
+    }
+}
+
+/// Returns the type whose `ValueName` is shown for `ty`, e.g. `T` for `Vec<T>`, `Option<T>` and
+/// `[T; N]`.
+pub fn value_type(ty: &syn::Type) -> &syn::Type {
+    if is_collection(ty) || is_option(ty) || matches!(ty, syn::Type::Array(_)) {
+        element_type(ty).unwrap_or(ty)
+    } else {
+        ty
+    }
+}
+
+/// Returns `true` if `ty` mentions any parameter of `generics`.
+///
+/// Such types cannot be used in the constants of the specs.
+pub fn uses_generics(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    let params = generics
+        .params
+        .iter()
+        .map(|param| match param {
+            syn::GenericParam::Type(param) => param.ident.clone(),
+            syn::GenericParam::Lifetime(param) => param.lifetime.ident.clone(),
+            syn::GenericParam::Const(param) => param.ident.clone(),
+        })
+        .collect::<Vec<_>>();
+    mentions(quote::ToTokens::to_token_stream(ty), &params)
+}
+
+fn mentions(tokens: proc_macro2::TokenStream, idents: &[syn::Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => idents.contains(&ident),
+        proc_macro2::TokenTree::Group(group) => mentions(group.stream(), idents),
+        _ => false,
+    })
+}
+
+/// Returns a hint of the type shown in help messages, e.g. `NUMBER` for `f64`.
+///
+/// The element type is inspected for collections, e.g. `Vec<f64>`.
+/// `None` is returned for types without an obvious hint such as `String`.
+pub fn hint(ty: &syn::Type) -> Option<&'static str> {
+    let segment = match last_segment(ty) {
+        Some(segment) => segment,
+        None => return hint(element_type(ty)?),
+    };
+    let hint = match segment.ident.to_string().as_str() {
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => "INTEGER",
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => "INTEGER",
+        "NonZeroU8" | "NonZeroU16" | "NonZeroU32" | "NonZeroU64" | "NonZeroUsize" => "INTEGER",
+        "f32" | "f64" => "NUMBER",
+        "bool" => "BOOL",
+        "char" => "CHAR",
+        "PathBuf" | "Path" => "PATH",
+        "IpAddr" | "Ipv4Addr" | "Ipv6Addr" => "ADDR",
+        "SocketAddr" => "ADDR:PORT",
+        _ => return hint(element_type(ty)?),
+    };
+    Some(hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints() {
+        let hint_of = |ty: &str| hint(&syn::parse_str(ty).unwrap());
+        assert_eq!(hint_of("f64"), Some("NUMBER"));
+        assert_eq!(hint_of("std::path::PathBuf"), Some("PATH"));
+        assert_eq!(hint_of("Vec<u8>"), Some("INTEGER"));
+        assert_eq!(hint_of("[f64; 3]"), Some("NUMBER"));
+        assert_eq!(hint_of("(u32, u32)"), Some("INTEGER"));
+        assert_eq!(hint_of("String"), None);
+        assert_eq!(hint_of("T"), None);
+    }
+
+    #[test]
+    fn value_types() {
+        let value_type_of = |ty: &str| {
+            let ty: syn::Type = syn::parse_str(ty).unwrap();
+            quote::ToTokens::to_token_stream(value_type(&ty)).to_string()
+        };
+        assert_eq!(value_type_of("Vec<Input>"), "Input");
+        assert_eq!(
+            value_type_of("Option<entrance::Output>"),
+            "entrance :: Output"
+        );
+        assert_eq!(value_type_of("[Input; 2]"), "Input");
+        assert_eq!(
+            value_type_of("HashMap<String, u32>"),
+            "HashMap < String , u32 >"
+        );
+
+        let generics: syn::Generics = syn::parse_str("<'a, P: FromStr, const N: usize>").unwrap();
+        let uses_generics_of = |ty: &str| uses_generics(&syn::parse_str(ty).unwrap(), &generics);
+        assert!(uses_generics_of("Vec<P>"));
+        assert!(uses_generics_of("&'a str"));
+        assert!(uses_generics_of("[u8; N]"));
+        assert!(!uses_generics_of("Input"));
+        assert!(!uses_generics_of("std::path::PathBuf"));
+    }
+
+    #[test]
+    fn collections() {
+        let is_collection_of = |ty: &str| is_collection(&syn::parse_str(ty).unwrap());
+        assert!(is_collection_of("Vec<String>"));
+        assert!(is_collection_of("std::collections::BTreeSet<u32>"));
+        assert!(!is_collection_of("[u32; 2]"));
+        assert!(!is_collection_of("String"));
+
+        let is_key_value_of = |ty: &str| is_key_value(&syn::parse_str(ty).unwrap());
+        assert!(is_key_value_of("HashMap<String, String>"));
+        assert!(is_key_value_of("Vec<(String, u32)>"));
+        assert!(!is_key_value_of("Vec<String>"));
+        assert!(!is_key_value_of("Vec<(u32, u32, u32)>"));
+    }
+}
@@ -4,15 +4,6 @@ use std::path::PathBuf;
 
 #[derive(Options, PartialEq)]
 enum Opts {
-    #[entrance(description = "Print help message")]
-    #[entrance(short = 'h')]
-    #[entrance(informative(entrance::help))]
-    Help,
-
-    #[entrance(description = "Print version infomation")]
-    #[entrance(informative(entrance::version))]
-    Version,
-
     #[entrance(description = "Use verbose output")]
     #[entrance(short = 'v')]
     Verbose,
@@ -30,7 +21,7 @@ struct Args {
 type Command = entrance::Command<Opts, Args>;
 
 fn main() {
-    let command = Command::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    let command: Command = entrance::crate_command!();
     let (opts, args) = command.parse_or_exit(env::args());
 
     if opts.contains(&Opts::Verbose) {
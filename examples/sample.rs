@@ -20,15 +20,6 @@ struct Args {
 
 #[derive(Options, PartialEq)]
 enum Opts {
-    #[entrance(description = "Print the help message")]
-    #[entrance(short = 'h')]
-    #[entrance(informative(entrance::help))]
-    Help,
-
-    #[entrance(description = "Use verbose output")]
-    #[entrance(informative(entrance::version))]
-    Version,
-
     #[entrance(description = "Use verbose output")]
     #[entrance(short = 'v')]
     Verbose,
@@ -1,11 +1,199 @@
-use crate::Result;
+use crate::error::check_count;
+use crate::{Error, Result};
+use std::marker::PhantomData;
 
 #[derive(Debug, Clone, Copy)]
-pub struct Arg {
-    pub name: &'static str,
-    pub description: &'static str,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Arg<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    /// A placeholder shown in the usage instead of the name, e.g. `FILE|-`.
+    pub value_name: Option<&'a str>,
+    /// A hint of the type shown after the name in the usage, e.g. `NUMBER` of `<num: NUMBER>`.
+    pub hint: Option<&'a str>,
+    /// The number of values taken by a fixed argument, e.g. `3` for `[f64; 3]`
+    pub num_values: usize,
+    /// Placeholders shown in the usage for each value of a fixed argument, e.g. `x y z`
+    pub value_names: &'a [&'a str],
+    /// The minimum number of values of the variable argument
+    pub min_values: usize,
+    /// The maximum number of values of the variable argument, or `None` for no limit
+    pub max_values: Option<usize>,
+    /// Whether the argument is omitted from the help message except the one of `--help-all`.
+    pub hidden: bool,
+    /// A message warned when the argument is given, e.g. `give it as --output instead`.
+    pub deprecated: Option<&'a str>,
 }
 
+impl<'a> Arg<'a> {
+    pub const fn new(name: &'a str, description: &'a str) -> Self {
+        Self {
+            name,
+            description,
+            value_name: None,
+            hint: None,
+            num_values: 1,
+            value_names: &[],
+            min_values: 0,
+            max_values: None,
+            hidden: false,
+            deprecated: None,
+        }
+    }
+
+    pub const fn with_value_name(mut self, value_name: &'a str) -> Self {
+        self.value_name = Some(value_name);
+        self
+    }
+
+    pub const fn with_hint(mut self, hint: &'a str) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    /// Makes the argument take `num` values.
+    pub const fn with_num_values(mut self, num: usize) -> Self {
+        self.num_values = num;
+        self
+    }
+
+    /// Makes the argument take a value for each of `names`, shown in the usage.
+    pub const fn with_value_names(mut self, names: &'a [&'a str]) -> Self {
+        self.num_values = names.len();
+        self.value_names = names;
+        self
+    }
+
+    pub const fn with_min_values(mut self, min: usize) -> Self {
+        self.min_values = min;
+        self
+    }
+
+    pub const fn with_max_values(mut self, max: usize) -> Self {
+        self.max_values = Some(max);
+        self
+    }
+
+    /// Omits the argument from the help message except the one of `--help-all`.
+    pub const fn with_hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    /// Marks the argument as deprecated, warning `message` when it is given.
+    pub const fn with_deprecation(mut self, message: &'a str) -> Self {
+        self.deprecated = Some(message);
+        self
+    }
+
+    /// Returns the text shown in the usage, e.g. `FILE` or `num: NUMBER`.
+    pub fn placeholder(&self) -> String {
+        match (self.value_name, self.hint) {
+            (Some(value_name), _) => value_name.to_string(),
+            (None, Some(hint)) => format!("{}: {}", self.name, hint),
+            (None, None) => self.name.to_string(),
+        }
+    }
+
+    /// Returns the text shown in the usage for a fixed argument, e.g. `<x> <y> <z>`.
+    pub fn usage(&self) -> String {
+        if self.value_names.is_empty() {
+            vec![format!("<{}>", self.placeholder()); self.num_values].join(" ")
+        } else {
+            let names: Vec<_> = self
+                .value_names
+                .iter()
+                .map(|name| format!("<{}>", name))
+                .collect();
+            names.join(" ")
+        }
+    }
+
+    /// Returns the text shown in the usage for the variable argument with its bounds,
+    /// e.g. `[files]...`, `<files>...` or `<files>{2,5}`.
+    pub fn variadic_placeholder(&self) -> String {
+        let placeholder = if self.min_values == 0 {
+            format!("[{}]", self.placeholder())
+        } else {
+            format!("<{}>", self.placeholder())
+        };
+        match (self.min_values, self.max_values) {
+            (0, None) | (1, None) => format!("{}...", placeholder),
+            (min, None) => format!("{}{{{},}}", placeholder, min),
+            (min, Some(max)) => format!("{}{{{},{}}}", placeholder, min, max),
+        }
+    }
+
+    /// Checks that the number of values of the variable argument is within the bounds.
+    pub fn check_values(&self, count: usize) -> Result<()> {
+        check_count(count, self.min_values, self.max_values)
+            .map_err(|message| Error::InvalidNumberOfValues(self.name.to_string(), message))
+    }
+
+    /// Returns the warning for the deprecated argument, or `None` if it is not deprecated.
+    pub fn deprecation_warning(&self) -> Option<String> {
+        let message = self.deprecated?;
+        Some(format!(
+            "argument `{}` is deprecated: {}",
+            self.name, message
+        ))
+    }
+}
+
+/// A type of values with a placeholder of its own in help messages, e.g. `FILE|-` for `Input`.
+///
+/// The derive macros show it for fields of the type, or their collections and `Option`s,
+/// unless `#[entrance(value_name = "...")]` is given.
+/// Fields whose types use generic parameters are not looked up.
+///
+/// # Example
+/// ```
+/// use entrance::{Arguments, ValueName};
+///
+/// struct Host(String);
+///
+/// impl std::str::FromStr for Host {
+///     type Err = std::convert::Infallible;
+///
+///     fn from_str(s: &str) -> Result<Self, Self::Err> {
+///         Ok(Host(s.to_string()))
+///     }
+/// }
+///
+/// impl ValueName for Host {
+///     const VALUE_NAME: &'static str = "HOST[:PORT]";
+/// }
+///
+/// #[derive(Arguments)]
+/// struct Args {
+///     server: Host,
+/// }
+///
+/// assert_eq!(Args::spec()[0].value_name, Some("HOST[:PORT]"));
+/// ```
+pub trait ValueName {
+    const VALUE_NAME: &'static str;
+}
+
+/// Looks up `ValueName::VALUE_NAME` of `T` in constants, falling back to `NoValueName`.
+///
+/// This is used by the derive macros.
+#[doc(hidden)]
+pub struct ValueNameOf<T: ?Sized>(PhantomData<T>);
+
+impl<T: ValueName + ?Sized> ValueNameOf<T> {
+    pub const VALUE_NAME: Option<&'static str> = Some(T::VALUE_NAME);
+}
+
+/// The value name of types not implementing `ValueName`, shadowed by the inherent constant of
+/// `ValueNameOf` for the ones implementing it.
+#[doc(hidden)]
+pub trait NoValueName {
+    const VALUE_NAME: Option<&'static str> = None;
+}
+
+impl<T: ?Sized> NoValueName for ValueNameOf<T> {}
+
 /// A trait for parsing and containing arguments.
 ///
 /// # Example
@@ -23,6 +211,19 @@ pub struct Arg {
 /// }
 /// ```
 ///
+/// Fields of types implementing `ValueName`, e.g. `Input` and `Output` or their collections,
+/// are shown with its placeholder like `<FILE|->` in the usage.
+///
+/// A field of an array or a tuple, e.g. `[f64; 3]` or `(u32, u32)`, takes a value for each
+/// element, and `#[entrance(value_names("x", "y", "z"))]` names them in the usage.
+///
+/// `#[entrance(variable_argument)]` makes a field take the rest of the arguments.
+/// It may be followed by other fields, which take the last arguments like `cp SRC... DEST`,
+/// and `min = 1` or `max = 3` bounds the number of its values.
+///
+/// `#[entrance(hidden)]` omits a field from the usage except the one of `--help-all`,
+/// and `#[entrance(deprecated = "...")]` warns the message when it is given.
+///
 /// # Limitation
 /// The derive macro for `Arguments` supports only a struct with named fields.
 /// Additionally, these fields should implement `FromStr`.
@@ -30,10 +231,17 @@ pub trait Arguments: Sized {
     fn parse<I: Iterator<Item = String>>(args: &mut I) -> Result<Self>;
 
     /// This associated function is for `HelpDisplay`.
-    fn spec() -> &'static [Arg];
+    fn spec() -> &'static [Arg<'static>];
 
     /// This associated function is for `HelpDisplay`.
-    fn var_spec() -> Option<Arg>;
+    fn var_spec() -> Option<Arg<'static>>;
+
+    /// Returns the number of arguments in `spec()` before the variable argument.
+    ///
+    /// The variable argument is the last one by default.
+    fn var_position() -> usize {
+        Self::spec().len()
+    }
 }
 
 impl Arguments for () {
@@ -41,11 +249,11 @@ impl Arguments for () {
         Ok(())
     }
 
-    fn spec() -> &'static [Arg] {
+    fn spec() -> &'static [Arg<'static>] {
         &[]
     }
 
-    fn var_spec() -> Option<Arg> {
+    fn var_spec() -> Option<Arg<'static>> {
         None
     }
 }
@@ -65,6 +273,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn usage() {
+        assert_eq!(Arg::new("num", "").usage(), "<num>");
+        let coords = Arg::new("coords", "").with_hint("NUMBER");
+        assert_eq!(
+            coords.with_num_values(2).usage(),
+            "<coords: NUMBER> <coords: NUMBER>"
+        );
+        assert_eq!(coords.with_value_names(&["x", "y"]).usage(), "<x> <y>");
+    }
+
+    #[test]
+    fn variadic_placeholder() {
+        let arg = Arg::new("files", "");
+        assert_eq!(arg.variadic_placeholder(), "[files]...");
+        assert_eq!(arg.with_min_values(1).variadic_placeholder(), "<files>...");
+        assert_eq!(arg.with_min_values(2).variadic_placeholder(), "<files>{2,}");
+        assert_eq!(
+            arg.with_max_values(3).variadic_placeholder(),
+            "[files]{0,3}"
+        );
+    }
+
+    #[test]
+    fn check_values() {
+        let arg = Arg::new("files", "").with_min_values(1).with_max_values(2);
+        assert!(arg.check_values(1).is_ok());
+        assert!(arg.check_values(2).is_ok());
+        assert_eq!(
+            arg.check_values(0).unwrap_err().to_string(),
+            "Invalid number of values for files: expected at least 1, but 0 given"
+        );
+        assert_eq!(
+            arg.check_values(3).unwrap_err().to_string(),
+            "Invalid number of values for files: expected at most 2, but 3 given"
+        );
+    }
+
+    #[test]
+    fn deprecation_warning() {
+        let arg = Arg::new("output", "");
+        assert_eq!(arg.deprecation_warning(), None);
+        assert_eq!(
+            arg.with_deprecation("give it as --output instead")
+                .deprecation_warning()
+                .unwrap(),
+            "argument `output` is deprecated: give it as --output instead"
+        );
+    }
+
     #[test]
     fn arguments_spec() {
         assert_eq!(<() as Arguments>::spec().len(), 0);
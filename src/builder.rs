@@ -0,0 +1,719 @@
+use crate::command::{find_builtin, non_empty, Builtin, Info};
+use crate::testing::Outcome;
+use crate::{parse_argument, parse_variable_argument};
+use crate::{Arg, ArgCursor, ColorChoice, Error, HelpDisplay, JsonSpec, MarkdownDisplay};
+use crate::{Opt, Result, Styles, ValueKind};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::str::FromStr;
+
+/// Helper struct for parsing command line arguments defined at runtime.
+///
+/// Unlike `Command`, options and arguments are registered with the instance itself,
+/// and the parsed values are stored in `Matches`.
+/// Options and arguments share their names in `Matches`.
+///
+/// # Example
+/// ```
+/// use entrance::{Arg, CommandBuilder, Opt};
+///
+/// let command = CommandBuilder::new("sample", "1.0.0")
+///     .option(Opt::new("verbose", "Use verbose output").with_short('v'))
+///     .option(Opt::new("jobs", "The number of jobs").with_short('j').with_value())
+///     .argument(Arg::new("num", "The number of lines"))
+///     .variable_argument(Arg::new("files", "Paths to files"));
+///
+/// let args = ["sample", "-vj4", "3", "a.txt", "b.txt"];
+/// let matches = command.parse(args.iter().map(|s| s.to_string())).unwrap();
+///
+/// assert!(matches.is_present("verbose"));
+/// assert_eq!(matches.get::<usize>("jobs").unwrap(), Some(4));
+/// assert_eq!(matches.get::<u32>("num").unwrap(), Some(3));
+/// assert_eq!(matches.values_of("files"), ["a.txt", "b.txt"]);
+/// ```
+#[derive(Debug)]
+pub struct CommandBuilder<'a> {
+    info: Info,
+    options: Vec<Opt<'a>>,
+    arguments: Vec<Arg<'a>>,
+    variable_argument: Option<Arg<'a>>,
+}
+
+impl<'a> CommandBuilder<'a> {
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            info: Info::new(name, version),
+            options: Vec::new(),
+            arguments: Vec::new(),
+            variable_argument: None,
+        }
+    }
+
+    /// Sets a one-line summary shown under the name in the help message.
+    ///
+    /// An empty string removes it.
+    pub fn about(mut self, about: &str) -> Self {
+        self.info.about = non_empty(about);
+        self
+    }
+
+    /// Sets a detailed description shown before the usage in the help message.
+    ///
+    /// An empty string removes it.
+    pub fn long_description(mut self, description: &str) -> Self {
+        self.info.long_description = non_empty(description);
+        self
+    }
+
+    /// Sets the authors shown under the name in the help message.
+    ///
+    /// An empty string removes it.
+    pub fn authors(mut self, authors: &str) -> Self {
+        self.info.authors = non_empty(authors);
+        self
+    }
+
+    /// Sets a text shown at the end of the help message, e.g. examples.
+    ///
+    /// An empty string removes it.
+    pub fn after_help(mut self, text: &str) -> Self {
+        self.info.after_help = non_empty(text);
+        self
+    }
+
+    /// Replaces the built-in option to print the help message.
+    ///
+    /// `None` disables the built-in option.
+    pub fn help_option(mut self, option: Option<Opt<'static>>) -> Self {
+        self.info.help_option = option;
+        self
+    }
+
+    /// Replaces the built-in option to print the help message including hidden options.
+    ///
+    /// `None` disables the built-in option.
+    pub fn help_all_option(mut self, option: Option<Opt<'static>>) -> Self {
+        self.info.help_all_option = option;
+        self
+    }
+
+    /// Replaces the built-in option to print the version.
+    ///
+    /// `None` disables the built-in option.
+    pub fn version_option(mut self, option: Option<Opt<'static>>) -> Self {
+        self.info.version_option = option;
+        self
+    }
+
+    /// Sets whether to color the help message and errors.
+    pub fn color(mut self, choice: ColorChoice) -> Self {
+        self.info.color = choice;
+        self
+    }
+
+    /// Replaces the escape sequences used for coloring.
+    pub fn styles(mut self, styles: Styles) -> Self {
+        self.info.styles = styles;
+        self
+    }
+
+    /// Prints only the usage and a hint for the help option instead of the whole help message
+    /// on errors in `parse_or_exit`.
+    pub fn concise_errors(mut self, enabled: bool) -> Self {
+        self.info.concise_errors = enabled;
+        self
+    }
+
+    /// Replaces the function which decides the exit code of `parse_or_exit` for an error.
+    ///
+    /// `Error::exit_code` is used by default.
+    pub fn exit_code(mut self, exit_code: fn(&Error) -> i32) -> Self {
+        self.info.exit_code = exit_code;
+        self
+    }
+
+    /// Replaces the function which receives warnings, e.g. for deprecated options.
+    ///
+    /// The warnings are printed to stderr by default.
+    pub fn warning_sink(mut self, sink: fn(&str)) -> Self {
+        self.info.warning_sink = sink;
+        self
+    }
+
+    /// Enables expanding `@path` arguments into the contents of the files before parsing.
+    ///
+    /// See `expand_response_files` for the format of the files.
+    pub fn response_files(mut self, enabled: bool) -> Self {
+        self.info.response_files = enabled;
+        self
+    }
+
+    /// Adds an option.
+    pub fn option(mut self, option: Opt<'a>) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    /// Adds a positional argument after the already added ones.
+    pub fn argument(mut self, argument: Arg<'a>) -> Self {
+        self.arguments.push(argument);
+        self
+    }
+
+    /// Sets the argument which takes all the remaining arguments.
+    ///
+    /// The number of the values is checked with `min_values` and `max_values` of the argument.
+    pub fn variable_argument(mut self, argument: Arg<'a>) -> Self {
+        self.variable_argument = Some(argument);
+        self
+    }
+
+    pub fn parse<I: Iterator<Item = String>>(&self, args: I) -> Result<Matches> {
+        match self.try_parse(args, self.info.color.for_stdout())? {
+            Outcome::Parsed(matches) => return Ok(matches),
+            Outcome::Help(message) => println!("{}", message),
+            Outcome::Version(message) => println!("{}", message),
+            Outcome::Informative(never, _) => match never {},
+        }
+        std::process::exit(0);
+    }
+
+    /// Parses the arguments without printing the help message or the version.
+    ///
+    /// `styled` decides whether the help message in the outcome is colored.
+    pub(crate) fn try_parse<I: Iterator<Item = String>>(
+        &self,
+        args: I,
+        styled: bool,
+    ) -> Result<Outcome<Matches, Infallible>> {
+        let hidden_arguments = self
+            .arguments
+            .iter()
+            .chain(&self.variable_argument)
+            .any(|arg| arg.hidden);
+        let builtin_options = self.info.builtin_options(&self.options, hidden_arguments);
+        let shorts = self
+            .options
+            .iter()
+            .chain(builtin_options.iter().map(|(opt, _)| opt))
+            .flat_map(|opt| opt.shorts());
+        let mut args = self.info.cursor(args, shorts)?;
+
+        let mut matches = Matches::default();
+        let mut error = None;
+        loop {
+            let option = match args.next_option() {
+                Ok(Some(option)) => option,
+                Ok(None) => break,
+                Err(err) => {
+                    error.get_or_insert(err);
+                    continue;
+                }
+            };
+            match find_builtin(&builtin_options, &option) {
+                Some(Builtin::Help) => {
+                    let help = self.help_message().styled(styled);
+                    return Ok(Outcome::Help(help.to_string()));
+                }
+                Some(Builtin::HelpAll) => {
+                    let help = self.help_all_message().styled(styled);
+                    return Ok(Outcome::Help(help.to_string()));
+                }
+                Some(Builtin::Version) => {
+                    return Ok(Outcome::Version(self.info.version_message()));
+                }
+                None => match self.options.iter().find(|opt| option.matches(opt)) {
+                    Some(opt) => {
+                        self.info.warn_deprecated(opt, &option);
+                        if let Err(err) = matches.add_option(opt, &mut args) {
+                            error.get_or_insert(err);
+                        }
+                    }
+                    None => {
+                        error.get_or_insert(Error::InvalidOption);
+                    }
+                },
+            }
+        }
+        if let Some(err) = error {
+            return Err(err);
+        }
+        for option in &self.options {
+            option.check_occurrences(matches.occurrences(option.long))?;
+        }
+
+        for argument in &self.arguments {
+            let values: Vec<_> = args.by_ref().take(argument.num_values).collect();
+            if values.len() < argument.num_values {
+                return Err(Error::InvalidNumberOfArguments);
+            }
+            self.info.warn_deprecated_argument(argument);
+            matches.values.insert(argument.name.to_string(), values);
+        }
+        if let Some(argument) = &self.variable_argument {
+            let values: Vec<_> = args.collect();
+            argument.check_values(values.len())?;
+            if !values.is_empty() {
+                self.info.warn_deprecated_argument(argument);
+            }
+            matches.values.insert(argument.name.to_string(), values);
+        }
+
+        Ok(Outcome::Parsed(matches))
+    }
+
+    pub fn parse_or_exit<I: Iterator<Item = String>>(&self, args: I) -> Matches {
+        match self.parse(args) {
+            Ok(matches) => matches,
+            Err(err) => {
+                self.info.exit_with_error(&err, self.help_message());
+            }
+        }
+    }
+
+    /// Returns the message printed by `parse_or_exit` for the error without colors.
+    pub fn error_message(&self, err: &Error) -> String {
+        self.info.error_message(err, self.help_message())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.info.version
+    }
+
+    pub fn help_message(&self) -> HelpDisplay<'_> {
+        HelpDisplay::new(
+            &self.info,
+            &self.options,
+            &self.arguments,
+            self.variable_argument,
+            self.arguments.len(),
+            false,
+        )
+    }
+
+    /// Returns the help message including hidden options, printed by `--help-all`.
+    pub fn help_all_message(&self) -> HelpDisplay<'_> {
+        HelpDisplay::new(
+            &self.info,
+            &self.options,
+            &self.arguments,
+            self.variable_argument,
+            self.arguments.len(),
+            true,
+        )
+    }
+
+    /// Returns the spec of the command as JSON described in `JsonSpec`.
+    pub fn json_spec(&self) -> JsonSpec<'_> {
+        JsonSpec::new(self.help_all_message())
+    }
+
+    /// Returns the reference of the command rendered as Markdown.
+    pub fn markdown(&self) -> MarkdownDisplay<'_> {
+        MarkdownDisplay::new(self.help_message())
+    }
+}
+
+/// Options and arguments parsed by `CommandBuilder`.
+///
+/// Options are looked up by their long names, and arguments by their names.
+#[derive(Debug, Default)]
+pub struct Matches {
+    occurrences: HashMap<String, usize>,
+    values: HashMap<String, Vec<String>>,
+}
+
+impl Matches {
+    fn add_option(&mut self, option: &Opt, args: &mut ArgCursor) -> Result<()> {
+        *self.occurrences.entry(option.long.to_string()).or_default() += 1;
+        let values = match option.value {
+            ValueKind::Flag => return Ok(()),
+            ValueKind::Required => option.take_values(args)?,
+            ValueKind::Optional => option.take_optional_value(args).into_iter().collect(),
+        };
+        if option.separator.is_some() {
+            for value in &values {
+                option.split_key_value(value)?;
+            }
+        }
+        self.values
+            .entry(option.long.to_string())
+            .or_default()
+            .extend(values);
+        Ok(())
+    }
+
+    /// Returns `true` if the option is given at least once.
+    pub fn is_present(&self, option: &str) -> bool {
+        self.occurrences(option) > 0
+    }
+
+    /// Returns how many times the option is given.
+    pub fn occurrences(&self, option: &str) -> usize {
+        self.occurrences.get(option).copied().unwrap_or(0)
+    }
+
+    /// Returns the raw value of the argument or the option.
+    ///
+    /// The first one is returned for an option given several times.
+    pub fn value_of(&self, name: &str) -> Option<&str> {
+        self.values_of(name).first().map(String::as_str)
+    }
+
+    /// Returns the raw values of the argument or the option.
+    ///
+    /// This is useful for the variable argument and options given several times.
+    pub fn values_of(&self, name: &str) -> &[String] {
+        self.values.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Parses the value of the argument or the option.
+    pub fn get<T>(&self, name: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + 'static,
+    {
+        self.value_of(name)
+            .map(|value| parse_argument(value.to_string()))
+            .transpose()
+    }
+
+    /// Parses all the values of the argument or the option.
+    pub fn get_all<T>(&self, name: &str) -> Result<Vec<T>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + 'static,
+    {
+        parse_variable_argument(self.values_of(name).iter().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn command() -> CommandBuilder<'static> {
+        CommandBuilder::new("sample", "1.0.0")
+            .option(Opt::new("verbose", "Use verbose output").with_short('v'))
+            .option(Opt::new("quiet", "Suppress outputs"))
+            .option(Opt::new("exclude", "Exclude a file").with_value())
+            .argument(Arg::new("num", "The number of lines"))
+            .variable_argument(Arg::new("files", "Paths to files"))
+    }
+
+    #[test]
+    fn parse() -> Result<()> {
+        let args = [
+            "sample",
+            "-vv",
+            "--exclude",
+            "c.txt",
+            "--exclude=d.txt",
+            "3",
+            "a.txt",
+            "b.txt",
+        ];
+        let matches = command().parse(args.iter().map(|s| s.to_string()))?;
+
+        assert_eq!(matches.occurrences("verbose"), 2);
+        assert_eq!(matches.values_of("exclude"), ["c.txt", "d.txt"]);
+        assert!(!matches.is_present("quiet"));
+        assert_eq!(matches.get::<f64>("num")?, Some(3.0));
+        assert_eq!(
+            matches.get_all::<PathBuf>("files")?,
+            vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")]
+        );
+
+        let args = ["sample", "--invalid", "3"];
+        let matches = command().parse(args.iter().map(|s| s.to_string()));
+        assert!(matches!(matches.unwrap_err(), Error::InvalidOption));
+
+        let args = ["sample", "3", "--exclude"];
+        let matches = command().parse(args.iter().map(|s| s.to_string()))?;
+        assert_eq!(matches.values_of("files"), ["--exclude"]);
+
+        let args = ["sample", "--exclude"];
+        let matches = command().parse(args.iter().map(|s| s.to_string()));
+        assert!(matches!(matches.unwrap_err(), Error::MissingValue(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn delimiter_and_occurrences() -> Result<()> {
+        let command = CommandBuilder::new("sample", "1.0.0")
+            .option(Opt::new("features", "Enable features").with_delimiter(','))
+            .option(
+                Opt::new("target", "Build for a target")
+                    .with_value()
+                    .with_min_occurrences(1),
+            );
+        let args = ["sample", "--features", "a,b", "--target=x", "--features=c"];
+        let matches = command.parse(args.iter().map(|s| s.to_string()))?;
+        assert_eq!(matches.values_of("features"), ["a", "b", "c"]);
+        assert_eq!(matches.occurrences("features"), 2);
+
+        let args = ["sample", "--features", "a"];
+        let err = command
+            .parse(args.iter().map(|s| s.to_string()))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid number of occurrences of --target: expected at least 1, but 0 given"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn key_and_optional_values() -> Result<()> {
+        let command = CommandBuilder::new("sample", "1.0.0").option(
+            Opt::new("define", "Define a macro")
+                .with_short('D')
+                .with_separator('='),
+        );
+        let args = ["sample", "-DA=1", "--define", "B="];
+        let matches = command.parse(args.iter().map(|s| s.to_string()))?;
+        assert_eq!(matches.values_of("define"), ["A=1", "B="]);
+
+        let command = command.option(Opt::new("color", "Use colors").with_implicit_value("auto"));
+        let args = ["sample", "--color", "--color=never", "-DA=1"];
+        let matches = command.parse(args.iter().map(|s| s.to_string()))?;
+        assert_eq!(matches.values_of("color"), ["auto", "never"]);
+
+        let args = ["sample", "-D", "A"];
+        let err = command.parse(args.iter().map(|s| s.to_string()));
+        assert!(matches!(err, Err(Error::InvalidKeyValue(..))));
+        Ok(())
+    }
+
+    #[test]
+    fn error_message() {
+        let err = Error::InvalidOption;
+        let command = command().version_option(None);
+        assert!(command
+            .error_message(&err)
+            .starts_with("Error: Invalid option\nUSAGE:\n"));
+
+        let command = command.concise_errors(true);
+        assert_eq!(
+            command.error_message(&err),
+            "\
+Error: Invalid option
+
+USAGE:
+    sample [OPTIONS] <num> [files]...
+
+For more information, try `--help`.
+"
+        );
+
+        let command = command.help_option(None).exit_code(|err| {
+            if err.exit_code() == crate::EX_USAGE {
+                2
+            } else {
+                1
+            }
+        });
+        assert_eq!(
+            command.error_message(&err),
+            "Error: Invalid option\n\nUSAGE:\n    sample [OPTIONS] <num> [files]...\n"
+        );
+        assert_eq!((command.info.exit_code)(&err), 2);
+        assert_eq!(err.exit_code(), crate::EX_USAGE);
+        assert_eq!(
+            Error::Config("a".to_string(), "b".to_string()).exit_code(),
+            crate::EX_CONFIG
+        );
+    }
+
+    #[test]
+    fn json_spec() {
+        let command = command().about("A \"sample\" command").version_option(None);
+        assert_eq!(
+            command.json_spec().to_string(),
+            r#"{
+  "schema_version": 1,
+  "name": "sample",
+  "version": "1.0.0",
+  "about": "A \"sample\" command",
+  "authors": null,
+  "options": [
+    {
+      "long": "verbose",
+      "short": "v",
+      "description": "Use verbose output",
+      "value": "flag",
+      "allow_hyphen_values": false,
+      "value_name": null,
+      "hidden": false,
+      "deprecated": null,
+      "aliases": [],
+      "visible_aliases": [],
+      "short_aliases": [],
+      "visible_short_aliases": [],
+      "deprecated_aliases": [],
+      "deprecated_short_aliases": [],
+      "delimiter": null,
+      "min_occurrences": 0,
+      "max_occurrences": null,
+      "separator": null,
+      "implicit_value": null
+    },
+    {
+      "long": "quiet",
+      "short": null,
+      "description": "Suppress outputs",
+      "value": "flag",
+      "allow_hyphen_values": false,
+      "value_name": null,
+      "hidden": false,
+      "deprecated": null,
+      "aliases": [],
+      "visible_aliases": [],
+      "short_aliases": [],
+      "visible_short_aliases": [],
+      "deprecated_aliases": [],
+      "deprecated_short_aliases": [],
+      "delimiter": null,
+      "min_occurrences": 0,
+      "max_occurrences": null,
+      "separator": null,
+      "implicit_value": null
+    },
+    {
+      "long": "exclude",
+      "short": null,
+      "description": "Exclude a file",
+      "value": "required",
+      "allow_hyphen_values": false,
+      "value_name": null,
+      "hidden": false,
+      "deprecated": null,
+      "aliases": [],
+      "visible_aliases": [],
+      "short_aliases": [],
+      "visible_short_aliases": [],
+      "deprecated_aliases": [],
+      "deprecated_short_aliases": [],
+      "delimiter": null,
+      "min_occurrences": 0,
+      "max_occurrences": null,
+      "separator": null,
+      "implicit_value": null
+    },
+    {
+      "long": "help",
+      "short": "h",
+      "description": "Print help information",
+      "value": "flag",
+      "allow_hyphen_values": false,
+      "value_name": null,
+      "hidden": false,
+      "deprecated": null,
+      "aliases": [],
+      "visible_aliases": [],
+      "short_aliases": [],
+      "visible_short_aliases": [],
+      "deprecated_aliases": [],
+      "deprecated_short_aliases": [],
+      "delimiter": null,
+      "min_occurrences": 0,
+      "max_occurrences": null,
+      "separator": null,
+      "implicit_value": null
+    }
+  ],
+  "arguments": [
+    {
+      "name": "num",
+      "description": "The number of lines",
+      "value_name": null,
+      "hint": null,
+      "num_values": 1,
+      "value_names": [],
+      "hidden": false,
+      "deprecated": null
+    }
+  ],
+  "variable_argument": {
+    "name": "files",
+    "description": "Paths to files",
+    "value_name": null,
+    "hint": null,
+    "num_values": 1,
+    "value_names": [],
+    "hidden": false,
+    "deprecated": null,
+    "position": 1,
+    "min_values": 0,
+    "max_values": null
+  },
+  "subcommands": []
+}
+"#
+        );
+
+        let command = CommandBuilder::new("empty", "0.1.0")
+            .help_option(None)
+            .version_option(None);
+        assert!(command
+            .json_spec()
+            .to_string()
+            .contains("\"options\": [],\n  \"arguments\": [],\n  \"variable_argument\": null,"));
+    }
+
+    #[test]
+    fn hidden_and_deprecated_arguments() -> Result<()> {
+        use std::cell::RefCell;
+
+        thread_local! {
+            static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        let command = CommandBuilder::new("sample", "1.0.0")
+            .version_option(None)
+            .argument(Arg::new("num", "").with_deprecation("use --num instead"))
+            .variable_argument(Arg::new("files", "").with_hidden())
+            .warning_sink(|warning| WARNINGS.with(|w| w.borrow_mut().push(warning.to_string())));
+        assert_eq!(command.help_message().usage(), "sample [OPTIONS] <num>");
+        assert_eq!(
+            command.help_all_message().usage(),
+            "sample [OPTIONS] <num> [files]..."
+        );
+
+        command.parse(["sample", "1"].iter().map(|s| s.to_string()))?;
+        WARNINGS.with(|w| {
+            assert_eq!(
+                *w.borrow(),
+                ["argument `num` is deprecated: use --num instead"]
+            )
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn runtime_strings() {
+        let names: Vec<String> = vec!["plugin-a".to_string(), "plugin-b".to_string()];
+        let command = names.iter().fold(
+            CommandBuilder::new("sample", "1.0.0").version_option(None),
+            |command, name| command.option(Opt::new(name, "Enable a plugin")),
+        );
+        assert_eq!(
+            command.help_message().to_string(),
+            "\
+USAGE:
+    sample [OPTIONS]
+
+OPTIONS:
+        --plugin-a    Enable a plugin
+        --plugin-b    Enable a plugin
+    -h, --help        Print help information
+"
+        );
+    }
+}
@@ -1,16 +1,45 @@
-use crate::Result;
-use crate::{Arguments, OptionItem, Options};
-use std::iter::Peekable;
+use crate::config::{check_shared_keys, parse_flag};
+use crate::options::merge_options;
+use crate::style::{paint, Painted};
+use crate::testing::Outcome;
+use crate::{expand_response_files, Error, Result};
+use crate::{Arg, ArgCursor, Arguments, ConfigSource, JsonSpec, MarkdownDisplay, Opt};
+use crate::{ColorChoice, OptionItem, Options, Styles};
+use crate::{ValueKind, ValueSource, ValueSources};
+use std::collections::HashMap;
 use std::marker::PhantomData;
 
 /// Helper struct for parsing command line arguments.
+///
+/// By default, `-h, --help` and `-V, --version` are handled by the command itself,
+/// and so is `--help-all` if any option or argument is hidden.
+/// Options declared in `Opts` with the same long name or short character take precedence
+/// over these built-in options.
 #[derive(Debug)]
 pub struct Command<Opts, Args> {
-    name: String,
-    version: String,
+    info: Info,
+    sources: Vec<Box<dyn ConfigSource>>,
     _phantom: PhantomData<(Opts, Args)>,
 }
 
+/// Options and arguments with their sources.
+pub(crate) type WithSources<Opts, Args> = (Vec<Opts>, Args, ValueSources);
+
+/// The default built-in option to print the help message.
+pub const HELP_OPTION: Opt<'static> = Opt::new("help", "Print help information").with_short('h');
+
+/// The default built-in option to print the help message including hidden options.
+///
+/// It is available only if any option or argument is hidden.
+pub const HELP_ALL_OPTION: Opt<'static> = Opt::new(
+    "help-all",
+    "Print help information including hidden options",
+);
+
+/// The default built-in option to print the version.
+pub const VERSION_OPTION: Opt<'static> =
+    Opt::new("version", "Print version information").with_short('V');
+
 impl<Opts, Args> Command<Opts, Args>
 where
     Opts: Options,
@@ -18,182 +47,770 @@ where
 {
     pub fn new(name: &str, version: &str) -> Self {
         Self {
-            name: name.to_string(),
-            version: version.to_string(),
+            info: Info::new(name, version),
+            sources: Vec::new(),
             _phantom: PhantomData,
         }
     }
 
-    pub fn parse<I: Iterator<Item = String>>(&self, args: I) -> Result<(Vec<Opts>, Args)> {
-        // Skip the first element (= program_name)
-        let mut args = args.skip(1).peekable();
-        let options = take_options(&mut args);
+    /// Sets a one-line summary shown under the name in the help message.
+    ///
+    /// An empty string removes it.
+    pub fn about(mut self, about: &str) -> Self {
+        self.info.about = non_empty(about);
+        self
+    }
+
+    /// Sets a detailed description shown before the usage in the help message.
+    ///
+    /// An empty string removes it.
+    pub fn long_description(mut self, description: &str) -> Self {
+        self.info.long_description = non_empty(description);
+        self
+    }
+
+    /// Sets the authors shown under the name in the help message.
+    ///
+    /// An empty string removes it.
+    pub fn authors(mut self, authors: &str) -> Self {
+        self.info.authors = non_empty(authors);
+        self
+    }
+
+    /// Sets a text shown at the end of the help message, e.g. examples.
+    ///
+    /// An empty string removes it.
+    pub fn after_help(mut self, text: &str) -> Self {
+        self.info.after_help = non_empty(text);
+        self
+    }
+
+    /// Replaces the built-in option to print the help message.
+    ///
+    /// `None` disables the built-in option.
+    pub fn help_option(mut self, option: Option<Opt<'static>>) -> Self {
+        self.info.help_option = option;
+        self
+    }
+
+    /// Replaces the built-in option to print the help message including hidden options.
+    ///
+    /// `None` disables the built-in option.
+    pub fn help_all_option(mut self, option: Option<Opt<'static>>) -> Self {
+        self.info.help_all_option = option;
+        self
+    }
+
+    /// Replaces the built-in option to print the version.
+    ///
+    /// `None` disables the built-in option.
+    pub fn version_option(mut self, option: Option<Opt<'static>>) -> Self {
+        self.info.version_option = option;
+        self
+    }
+
+    /// Sets whether to color the help message and errors.
+    pub fn color(mut self, choice: ColorChoice) -> Self {
+        self.info.color = choice;
+        self
+    }
+
+    /// Replaces the escape sequences used for coloring.
+    pub fn styles(mut self, styles: Styles) -> Self {
+        self.info.styles = styles;
+        self
+    }
+
+    /// Prints only the usage and a hint for the help option instead of the whole help message
+    /// on errors in `parse_or_exit`.
+    pub fn concise_errors(mut self, enabled: bool) -> Self {
+        self.info.concise_errors = enabled;
+        self
+    }
+
+    /// Replaces the function which decides the exit code of `parse_or_exit` for an error.
+    ///
+    /// `Error::exit_code` is used by default.
+    pub fn exit_code(mut self, exit_code: fn(&Error) -> i32) -> Self {
+        self.info.exit_code = exit_code;
+        self
+    }
 
-        let opts: Vec<_> = options.into_iter().map(Opts::parse).collect();
+    /// Replaces the function which receives warnings, e.g. for deprecated options.
+    ///
+    /// The warnings are printed to stderr by default.
+    pub fn warning_sink(mut self, sink: fn(&str)) -> Self {
+        self.info.warning_sink = sink;
+        self
+    }
+
+    /// Enables expanding `@path` arguments into the contents of the files before parsing.
+    ///
+    /// See `expand_response_files` for the format of the files.
+    pub fn response_files(mut self, enabled: bool) -> Self {
+        self.info.response_files = enabled;
+        self
+    }
+
+    /// Adds a source of values for options and arguments missing from the command line.
+    ///
+    /// The command line takes precedence over all the sources, and a source added earlier
+    /// takes precedence over ones added later.
+    ///
+    /// # Example
+    /// ```
+    /// use entrance::{Command, EnvSource, KeyValueFile};
+    ///
+    /// let command = Command::<(), ()>::new("sample", "1.0.0")
+    ///     .config_source(EnvSource::new("SAMPLE"))
+    ///     .config_source("level = 3".parse::<KeyValueFile>()?);
+    /// # Ok::<(), entrance::Error>(())
+    /// ```
+    pub fn config_source<S: ConfigSource + 'static>(mut self, source: S) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    pub fn parse<I: Iterator<Item = String>>(&self, args: I) -> Result<(Vec<Opts>, Args)> {
+        self.parse_with_sources(args)
+            .map(|(opts, args, _)| (opts, args))
+    }
 
-        // If opts contains any informative option, trigger the callback function and exit
+    /// Parses the arguments like `parse`, also reporting where each value came from.
+    pub fn parse_with_sources<I: Iterator<Item = String>>(
+        &self,
+        args: I,
+    ) -> Result<(Vec<Opts>, Args, ValueSources)> {
+        // If any informative option is given, trigger the callback function and exit
         // immediately.
-        for opt in &opts {
-            if let Ok(opt) = opt {
-                if opt.is_informative() {
-                    opt.trigger_informative(self);
-                    std::process::exit(0);
+        match self.try_parse(args, self.info.color.for_stdout())? {
+            Outcome::Parsed(parsed) => return Ok(parsed),
+            Outcome::Help(message) => println!("{}", message),
+            Outcome::Version(_) => crate::version(self),
+            Outcome::Informative(opt, _) => opt.trigger_informative(self),
+        }
+        std::process::exit(0);
+    }
+
+    /// Parses the arguments without triggering informative options.
+    ///
+    /// `styled` decides whether the help message in the outcome is colored.
+    pub(crate) fn try_parse<I: Iterator<Item = String>>(
+        &self,
+        args: I,
+        styled: bool,
+    ) -> Result<Outcome<WithSources<Opts, Args>, Opts>> {
+        let builtin_options = self
+            .info
+            .builtin_options(Opts::spec(), has_hidden::<Args>());
+        let shorts = Opts::spec()
+            .iter()
+            .chain(builtin_options.iter().map(|(opt, _)| opt))
+            .flat_map(|opt| opt.shorts());
+        let mut args = self.info.cursor(args, shorts)?;
+        if !self.sources.is_empty() {
+            check_shared_keys::<Opts, Args>()?;
+        }
+
+        let mut sources = ValueSources::default();
+        let mut counts = HashMap::new();
+        let mut opts = Vec::new();
+        loop {
+            let opt = match args.next_option() {
+                Ok(Some(option)) => match find_builtin(&builtin_options, &option) {
+                    Some(builtin) => Ok(Parsed::Builtin(builtin)),
+                    None => {
+                        if let Some(opt) = Opts::spec().iter().find(|opt| option.matches(opt)) {
+                            sources.insert_option(opt.long, ValueSource::CommandLine);
+                            *counts.entry(opt.long).or_insert(0) += 1;
+                            self.info.warn_deprecated(opt, &option);
+                        }
+                        Opts::parse(option, &mut args).map(Parsed::Option)
+                    }
+                },
+                Ok(None) => break,
+                Err(err) => Err(err),
+            };
+            opts.push(opt);
+        }
+
+        let informative = opts.iter().position(|opt| match opt {
+            Ok(Parsed::Builtin(_)) => true,
+            Ok(Parsed::Option(opt)) => opt.is_informative(),
+            Err(_) => false,
+        });
+        if let Some(i) = informative {
+            return Ok(match opts.swap_remove(i)? {
+                Parsed::Builtin(Builtin::Help) => {
+                    Outcome::Help(self.help_message().styled(styled).to_string())
                 }
+                Parsed::Builtin(Builtin::HelpAll) => {
+                    Outcome::Help(self.help_all_message().styled(styled).to_string())
+                }
+                Parsed::Builtin(Builtin::Version) => Outcome::Version(self.info.version_message()),
+                // The output is captured only by `try_parse_from`, which triggers the option.
+                Parsed::Option(opt) => Outcome::Informative(opt, String::new()),
+            });
+        }
+
+        let mut opts = opts
+            .into_iter()
+            .filter_map(|opt| match opt {
+                Ok(Parsed::Option(opt)) => Some(Ok(opt)),
+                Ok(Parsed::Builtin(_)) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.fill_options(&mut opts, &mut sources, &mut counts)?;
+        for spec in Opts::spec() {
+            spec.check_occurrences(counts.get(spec.long).copied().unwrap_or(0))?;
+        }
+        let opts = merge_options(opts);
+        let mut positionals = self.fill_arguments(args.collect(), &mut sources);
+        for spec in Args::spec().iter().chain(&Args::var_spec()) {
+            if sources.argument(spec.name) == ValueSource::CommandLine {
+                self.info.warn_deprecated_argument(spec);
             }
         }
+        Ok(Outcome::Parsed((
+            opts,
+            Args::parse(&mut positionals)?,
+            sources,
+        )))
+    }
 
-        let opts: Result<Vec<_>> = opts.into_iter().collect();
-        Ok((opts?, Args::parse(&mut args)?))
+    /// Returns the values of `key` from the first source which has it.
+    fn lookup(&self, key: &str) -> Option<(Vec<String>, ValueSource)> {
+        self.sources.iter().find_map(|source| {
+            let values = source.values(key);
+            if values.is_empty() {
+                None
+            } else {
+                Some((values, source.kind()))
+            }
+        })
+    }
+
+    /// Adds the options missing from the command line from the config sources.
+    fn fill_options(
+        &self,
+        opts: &mut Vec<Opts>,
+        sources: &mut ValueSources,
+        counts: &mut HashMap<&'static str, usize>,
+    ) -> Result<()> {
+        for spec in Opts::spec() {
+            if sources.option(spec.long) != ValueSource::Default {
+                continue;
+            }
+            let (values, source) = match self.lookup(spec.long) {
+                Some(found) => found,
+                None => continue,
+            };
+            for value in values {
+                let arg = if spec.value != ValueKind::Flag {
+                    format!("--{}={}", spec.long, value)
+                } else if parse_flag(spec.long, &value)? {
+                    format!("--{}", spec.long)
+                } else {
+                    continue;
+                };
+                let mut cursor = ArgCursor::new(std::iter::once(arg));
+                if let Some(option) = cursor.next_option()? {
+                    let opt = Opts::parse(option, &mut cursor)?;
+                    // Informative options are triggered only from the command line.
+                    if !opt.is_informative() {
+                        opts.push(opt);
+                        sources.insert_option(spec.long, source);
+                        *counts.entry(spec.long).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the arguments missing from the command line from the config sources.
+    ///
+    /// The values of the variable argument are inserted at its position, so it may be followed by
+    /// other arguments.
+    fn fill_arguments(
+        &self,
+        mut positionals: Vec<String>,
+        sources: &mut ValueSources,
+    ) -> std::vec::IntoIter<String> {
+        let given = positionals.len();
+        let fixed: usize = Args::spec().iter().map(|spec| spec.num_values).sum();
+        if let Some(spec) = Args::var_spec().filter(|_| given > fixed) {
+            for spec in Args::spec() {
+                sources.insert_argument(spec.name, ValueSource::CommandLine);
+            }
+            sources.insert_argument(spec.name, ValueSource::CommandLine);
+            return positionals.into_iter();
+        }
+        let mut offset = 0;
+        for spec in Args::spec() {
+            offset += spec.num_values;
+            if offset <= given {
+                sources.insert_argument(spec.name, ValueSource::CommandLine);
+                continue;
+            }
+            match self.lookup(spec.name) {
+                // Values only partially given are left to `Arguments::parse` as well.
+                Some((values, source))
+                    if offset - spec.num_values >= given && values.len() >= spec.num_values =>
+                {
+                    positionals.extend(values.into_iter().take(spec.num_values));
+                    sources.insert_argument(spec.name, source);
+                }
+                // Leave the error on the missing argument to `Arguments::parse`.
+                _ => return positionals.into_iter(),
+            }
+        }
+        if let Some(spec) = Args::var_spec() {
+            if let Some((values, source)) = self.lookup(spec.name) {
+                let at: usize = Args::spec()[..Args::var_position()]
+                    .iter()
+                    .map(|spec| spec.num_values)
+                    .sum();
+                positionals.splice(at..at, values);
+                sources.insert_argument(spec.name, source);
+            }
+        }
+        positionals.into_iter()
     }
 
     pub fn parse_or_exit<I: Iterator<Item = String>>(&self, args: I) -> (Vec<Opts>, Args) {
         match self.parse(args) {
             Ok(retval) => retval,
             Err(err) => {
-                eprintln!("\x1b[31mError:\x1b[m {}", err);
-                eprintln!("{}", self.help_message());
-                std::process::exit(1);
+                self.info.exit_with_error(&err, self.help_message());
             }
         }
     }
 
     pub fn name(&self) -> &str {
-        &self.name
+        &self.info.name
     }
 
     pub fn version(&self) -> &str {
-        &self.version
+        &self.info.version
     }
 
-    pub fn help_message(&self) -> HelpDisplay<Opts, Args> {
-        HelpDisplay::new(self)
+    pub fn help_message(&self) -> HelpDisplay<'_> {
+        HelpDisplay::new(
+            &self.info,
+            Opts::spec(),
+            Args::spec(),
+            Args::var_spec(),
+            Args::var_position(),
+            false,
+        )
+    }
+
+    /// Returns the help message including hidden options, printed by `--help-all`.
+    pub fn help_all_message(&self) -> HelpDisplay<'_> {
+        HelpDisplay::new(
+            &self.info,
+            Opts::spec(),
+            Args::spec(),
+            Args::var_spec(),
+            Args::var_position(),
+            true,
+        )
+    }
+
+    /// Returns the message printed by `parse_or_exit` for the error without colors.
+    pub fn error_message(&self, err: &Error) -> String {
+        self.info.error_message(err, self.help_message())
+    }
+
+    /// Returns the help message colored according to `color` for stdout.
+    pub(crate) fn styled_help_message(&self) -> HelpDisplay<'_> {
+        let styled = self.info.color.for_stdout() && !crate::testing::capturing();
+        self.help_message().styled(styled)
+    }
+
+    /// Returns the spec of the command as JSON described in `JsonSpec`.
+    pub fn json_spec(&self) -> JsonSpec<'_> {
+        JsonSpec::new(self.help_all_message())
+    }
+
+    /// Returns the reference of the command rendered as Markdown.
+    pub fn markdown(&self) -> MarkdownDisplay<'_> {
+        MarkdownDisplay::new(self.help_message())
     }
 }
 
-fn take_options<I: Iterator<Item = String>>(args: &mut Peekable<I>) -> Vec<OptionItem> {
-    let mut options = Vec::new();
-    while let Some(arg) = args.peek() {
-        if arg.starts_with("--") {
-            if arg.len() == 2 {
-                break;
-            }
-            options.push(OptionItem::Long(arg[2..].to_string()));
-        } else if arg.starts_with('-') {
-            if arg.len() == 1 {
-                break;
-            }
-            for c in arg[1..].chars() {
-                options.push(OptionItem::Short(c));
-            }
+/// Texts and built-in options shared by `Command` and `CommandBuilder`.
+#[derive(Debug)]
+pub(crate) struct Info {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    pub(crate) about: Option<String>,
+    pub(crate) long_description: Option<String>,
+    pub(crate) authors: Option<String>,
+    pub(crate) after_help: Option<String>,
+    pub(crate) help_option: Option<Opt<'static>>,
+    pub(crate) help_all_option: Option<Opt<'static>>,
+    pub(crate) version_option: Option<Opt<'static>>,
+    pub(crate) response_files: bool,
+    pub(crate) color: ColorChoice,
+    pub(crate) styles: Styles,
+    pub(crate) concise_errors: bool,
+    pub(crate) exit_code: fn(&Error) -> i32,
+    pub(crate) warning_sink: fn(&str),
+}
+
+impl Info {
+    pub(crate) fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            about: None,
+            long_description: None,
+            authors: None,
+            after_help: None,
+            help_option: Some(HELP_OPTION),
+            help_all_option: Some(HELP_ALL_OPTION),
+            version_option: Some(VERSION_OPTION),
+            response_files: false,
+            color: ColorChoice::Auto,
+            styles: Styles::default(),
+            concise_errors: false,
+            exit_code: Error::exit_code,
+            warning_sink: print_warning,
+        }
+    }
+
+    /// Creates a cursor over the arguments following the program name.
+    pub(crate) fn cursor<I, S>(&self, args: I, shorts: S) -> Result<ArgCursor>
+    where
+        I: Iterator<Item = String>,
+        S: IntoIterator<Item = char>,
+    {
+        // Skip the first element (= program_name)
+        let args = args.skip(1);
+        let cursor = if self.response_files {
+            ArgCursor::new(expand_response_files(args)?)
         } else {
-            break;
+            ArgCursor::new(args)
+        };
+        Ok(cursor.with_shorts(shorts))
+    }
+
+    /// Returns the built-in options which are not overridden by `options`.
+    ///
+    /// A built-in option loses its short character when `options` uses the same one.
+    /// `--help-all` is included if any option is hidden, or `hidden_arguments` is `true`.
+    pub(crate) fn builtin_options(
+        &self,
+        options: &[Opt],
+        hidden_arguments: bool,
+    ) -> Vec<(Opt<'static>, Builtin)> {
+        let help_all_option = self
+            .help_all_option
+            .filter(|_| hidden_arguments || options.iter().any(|opt| opt.hidden));
+        let builtins = [
+            (self.help_option, Builtin::Help),
+            (help_all_option, Builtin::HelpAll),
+            (self.version_option, Builtin::Version),
+        ];
+        builtins
+            .iter()
+            .filter_map(|&(builtin, kind)| {
+                let mut builtin = builtin?;
+                if options.iter().any(|opt| opt.has_long(builtin.long)) {
+                    return None;
+                }
+                let short = builtin.short;
+                if options
+                    .iter()
+                    .any(|opt| short.is_some_and(|c| opt.has_short(c)))
+                {
+                    builtin.short = None;
+                }
+                Some((builtin, kind))
+            })
+            .collect()
+    }
+
+    /// Returns `options` followed by the built-in options which are not overridden.
+    pub(crate) fn with_builtins<'a>(
+        &self,
+        options: &[Opt<'a>],
+        hidden_arguments: bool,
+    ) -> Vec<Opt<'a>> {
+        let builtins = self.builtin_options(options, hidden_arguments);
+        options
+            .iter()
+            .copied()
+            .chain(builtins.into_iter().map(|(opt, _)| opt))
+            .collect()
+    }
+
+    /// Passes the warning to the sink if `arg` is deprecated.
+    pub(crate) fn warn_deprecated_argument(&self, arg: &Arg) {
+        if let Some(warning) = arg.deprecation_warning() {
+            (self.warning_sink)(&warning);
+        }
+    }
+
+    /// Passes the warning to the sink if `opt` given as `given` is deprecated.
+    pub(crate) fn warn_deprecated(&self, opt: &Opt, given: &OptionItem) {
+        if let Some(warning) = opt.deprecation_warning(given) {
+            (self.warning_sink)(&warning);
+        }
+    }
+
+    /// Prints the error to stderr and exits with the exit code for it.
+    pub(crate) fn exit_with_error(&self, err: &Error, help: HelpDisplay) -> ! {
+        let styled = self.color.for_stderr();
+        eprint!("{}", self.error_message(err, help.styled(styled)));
+        std::process::exit((self.exit_code)(err));
+    }
+
+    /// Formats the error followed by the help message, or the usage in the concise mode.
+    pub(crate) fn error_message(&self, err: &Error, help: HelpDisplay) -> String {
+        let mut message = format!(
+            "{} {}\n",
+            paint(help.styles.map(|styles| styles.error), "Error:"),
+            err
+        );
+        if !self.concise_errors {
+            message += &format!("{}\n", help);
+            return message;
+        }
+
+        message += &format!("\n{}\n    {}\n", help.heading("USAGE:"), help.usage());
+        let help_option = self
+            .help_option
+            .filter(|opt| help.options.iter().any(|o| o.long == opt.long));
+        if let Some(opt) = help_option {
+            message += &format!("\nFor more information, try `--{}`.\n", opt.long);
         }
-        args.next(); // Consume an argument
+        message
     }
-    options
+
+    pub(crate) fn version_message(&self) -> String {
+        format!("{} {}", self.name, self.version)
+    }
+}
+
+/// Returns `true` if any argument of `Args` is hidden.
+fn has_hidden<Args: Arguments>() -> bool {
+    Args::spec()
+        .iter()
+        .chain(&Args::var_spec())
+        .any(|arg| arg.hidden)
+}
+
+fn print_warning(warning: &str) {
+    eprintln!("Warning: {}", warning);
+}
+
+pub(crate) fn non_empty(s: &str) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Builtin {
+    Help,
+    HelpAll,
+    Version,
+}
+
+pub(crate) fn find_builtin(builtins: &[(Opt, Builtin)], option: &OptionItem) -> Option<Builtin> {
+    builtins
+        .iter()
+        .find(|(opt, _)| option.matches(opt))
+        .map(|&(_, builtin)| builtin)
+}
+
+enum Parsed<Opts> {
+    Option(Opts),
+    Builtin(Builtin),
 }
 
 /// Helper struct for printing help messages with `format!` and `{}`.
 #[derive(Debug)]
-pub struct HelpDisplay<'a, Opts, Args>(&'a Command<Opts, Args>);
+pub struct HelpDisplay<'a> {
+    pub(crate) info: &'a Info,
+    pub(crate) options: Vec<Opt<'a>>,
+    pub(crate) arguments: Vec<Arg<'a>>,
+    pub(crate) variable_argument: Option<Arg<'a>>,
+    pub(crate) variable_position: usize,
+    pub(crate) styles: Option<Styles>,
+}
+
+impl<'a> HelpDisplay<'a> {
+    pub(crate) fn new(
+        info: &'a Info,
+        options: &[Opt<'a>],
+        arguments: &'a [Arg<'a>],
+        variable_argument: Option<Arg<'a>>,
+        variable_position: usize,
+        show_hidden: bool,
+    ) -> Self {
+        let hidden_arguments = arguments
+            .iter()
+            .chain(&variable_argument)
+            .any(|arg| arg.hidden);
+        let options = info
+            .with_builtins(options, hidden_arguments)
+            .into_iter()
+            .filter(|opt| show_hidden || !opt.hidden)
+            .collect();
+        let shown = |arg: &&Arg| show_hidden || !arg.hidden;
+        let variable_position = arguments[..variable_position.min(arguments.len())]
+            .iter()
+            .filter(shown)
+            .count();
+        let arguments = arguments.iter().filter(shown).copied().collect();
+        let variable_argument = variable_argument.filter(|arg| shown(&arg));
+        Self {
+            info,
+            options,
+            arguments,
+            variable_argument,
+            variable_position,
+            styles: None,
+        }
+    }
+
+    /// Enables coloring with the styles of the command.
+    pub fn styled(mut self, enabled: bool) -> Self {
+        self.styles = Some(self.info.styles).filter(|_| enabled);
+        self
+    }
+
+    fn heading(&self, heading: &'static str) -> Painted<&'static str> {
+        paint(self.styles.map(|styles| styles.heading), heading)
+    }
+
+    fn literal<T: std::fmt::Display>(&self, literal: T) -> Painted<T> {
+        paint(self.styles.map(|styles| styles.literal), literal)
+    }
+
+    /// Returns the positional arguments in order, with `true` for the variable argument.
+    pub(crate) fn positionals(&self) -> Vec<(&Arg<'a>, bool)> {
+        let mut positionals: Vec<_> = self.arguments.iter().map(|arg| (arg, false)).collect();
+        if let Some(arg) = &self.variable_argument {
+            let position = self.variable_position.min(positionals.len());
+            positionals.insert(position, (arg, true));
+        }
+        positionals
+    }
+}
 
-impl<'a, Opts, Args> HelpDisplay<'a, Opts, Args> {
-    fn new(command: &'a Command<Opts, Args>) -> Self {
-        Self(command)
+impl<'a> HelpDisplay<'a> {
+    /// Returns the usage line without indentation.
+    pub(crate) fn usage(&self) -> String {
+        let mut usage = self.info.name.clone();
+        if !self.options.is_empty() {
+            usage += " [OPTIONS]";
+        }
+        for (arg, variadic) in self.positionals() {
+            if variadic {
+                usage += &format!(" {}", arg.variadic_placeholder());
+            } else {
+                usage += &format!(" {}", arg.usage());
+            }
+        }
+        usage
     }
 }
 
-impl<'a, Opts, Args> std::fmt::Display for HelpDisplay<'a, Opts, Args>
-where
-    Opts: Options,
-    Args: Arguments,
-{
+impl<'a> HelpDisplay<'a> {
+    fn write_options(&self, f: &mut std::fmt::Formatter, spacer: &str) -> std::fmt::Result {
+        let longs: Vec<_> = self.options.iter().map(|opt| opt.long_usage()).collect();
+        let longest_length = match longs.iter().map(String::len).max() {
+            Some(length) => length,
+            None => return Ok(()),
+        };
+        writeln!(f)?;
+        writeln!(f, "{}", self.heading("OPTIONS:"))?;
+        let has_short = self.options.iter().any(|opt| opt.short.is_some());
+        for (opt, long) in self.options.iter().zip(&longs) {
+            write!(f, "{}", spacer)?;
+            let name = match opt.short {
+                Some(short) => format!("-{}, --{}", short, long),
+                None => {
+                    if has_short {
+                        write!(f, "    ")?;
+                    }
+                    format!("--{}", long)
+                }
+            };
+            writeln!(
+                f,
+                "{}{:pad$}{spacer}{}",
+                self.literal(name),
+                "",
+                opt.description_with_aliases(),
+                spacer = spacer,
+                pad = longest_length - long.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> std::fmt::Display for HelpDisplay<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         const SPACER: &str = "    ";
 
-        writeln!(f, "USAGE:")?;
-        write!(f, "{indent}{}", self.0.name, indent = SPACER)?;
-        if !Opts::spec().is_empty() {
-            write!(f, " [OPTIONS]")?;
-        }
-        for arg in Args::spec() {
-            write!(f, " <{}>", arg.name)?;
+        let info = self.info;
+        if info.about.is_some() || info.authors.is_some() {
+            writeln!(f, "{}", info.version_message())?;
+            for line in info.authors.iter().chain(&info.about) {
+                writeln!(f, "{}", line)?;
+            }
+            writeln!(f)?;
         }
-        if let Some(args) = Args::var_spec() {
-            write!(f, " [{}]...", args.name)?;
+        if let Some(description) = &info.long_description {
+            writeln!(f, "{}", description)?;
+            writeln!(f)?;
         }
-        writeln!(f)?;
 
-        format_options(f, SPACER, Opts::spec())?;
+        writeln!(f, "{}", self.heading("USAGE:"))?;
+        writeln!(f, "{}{}", SPACER, self.usage())?;
 
-        let var_args_spec = Args::var_spec();
-        if let Some(longest_length) = Args::spec()
-            .iter()
-            .chain(&var_args_spec)
-            .map(|arg| arg.name.len())
-            .max()
-        {
+        self.write_options(f, SPACER)?;
+
+        let positionals = self.positionals();
+        if let Some(longest_length) = positionals.iter().map(|(arg, _)| arg.name.len()).max() {
             writeln!(f)?;
-            writeln!(f, "ARGS:")?;
-            for arg in Args::spec().iter().chain(&var_args_spec) {
+            writeln!(f, "{}", self.heading("ARGS:"))?;
+            for (arg, _) in positionals {
                 writeln!(
                     f,
-                    "{spacer}{:<width$}{spacer}{}",
-                    arg.name,
+                    "{spacer}{}{:pad$}{spacer}{}",
+                    self.literal(arg.name),
+                    "",
                     arg.description,
                     spacer = SPACER,
-                    width = longest_length
+                    pad = longest_length - arg.name.len()
                 )?;
             }
         }
 
-        Ok(())
-    }
-}
-
-fn format_options(
-    f: &mut std::fmt::Formatter,
-    spacer: &str,
-    opts: &[crate::Opt],
-) -> std::fmt::Result {
-    if let Some(longest_length) = opts.iter().map(|opt| opt.long.len()).max() {
-        writeln!(f)?;
-        writeln!(f, "OPTIONS:")?;
-        if opts.iter().any(|opt| opt.short.is_some()) {
-            for opt in opts {
-                writeln!(
-                    f,
-                    "{spacer}{} --{:<width$}{spacer}{}",
-                    opt.short
-                        .map(|f| format!("-{},", f))
-                        .unwrap_or_else(|| "   ".to_string()),
-                    opt.long,
-                    opt.description,
-                    spacer = spacer,
-                    width = longest_length
-                )?;
-            }
-        } else {
-            for opt in opts {
-                writeln!(
-                    f,
-                    "{spacer}--{:<width$}{spacer}{}",
-                    opt.long,
-                    opt.description,
-                    spacer = spacer,
-                    width = longest_length
-                )?;
-            }
+        if let Some(after_help) = &info.after_help {
+            writeln!(f)?;
+            writeln!(f, "{}", after_help)?;
         }
-    }
 
-    Ok(())
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{parse_argument, Arg};
+    use crate::parse_argument;
     use std::path::PathBuf;
 
     struct Args {
@@ -211,25 +828,16 @@ mod tests {
             })
         }
 
-        fn spec() -> &'static [Arg] {
+        fn spec() -> &'static [Arg<'static>] {
             const ARGS: [Arg; 3] = [
-                Arg {
-                    name: "arg1",
-                    description: "This is parsed as String",
-                },
-                Arg {
-                    name: "arg2",
-                    description: "This is parsed as i32",
-                },
-                Arg {
-                    name: "arg3",
-                    description: "This is parsed as PathBuf",
-                },
+                Arg::new("arg1", "This is parsed as String"),
+                Arg::new("arg2", "This is parsed as i32"),
+                Arg::new("arg3", "This is parsed as PathBuf"),
             ];
             &ARGS
         }
 
-        fn var_spec() -> Option<Arg> {
+        fn var_spec() -> Option<Arg<'static>> {
             None
         }
     }
@@ -250,8 +858,10 @@ mod tests {
 
     #[test]
     fn format_usage() {
-        let command: Command<(), Args> = Command::new("sample", "1.0.0");
-        let usage = HelpDisplay::new(&command);
+        let command: Command<(), Args> = Command::new("sample", "1.0.0")
+            .help_option(None)
+            .version_option(None);
+        let usage = command.help_message();
         assert_eq!(
             usage.to_string(),
             "\
@@ -266,4 +876,96 @@ ARGS:
             .to_string()
         );
     }
+
+    #[test]
+    fn format_builtin_options() {
+        struct Opts;
+
+        impl Options for Opts {
+            fn parse(_: OptionItem, _: &mut ArgCursor) -> Result<Self> {
+                Ok(Opts)
+            }
+
+            fn is_informative(&self) -> bool {
+                false
+            }
+
+            fn trigger_informative<Args: Arguments>(&self, _: &Command<Self, Args>) {}
+
+            fn spec() -> &'static [Opt<'static>] {
+                const OPTS: [Opt; 1] = [Opt::new("verbose", "Use verbose output").with_short('V')];
+                &OPTS
+            }
+        }
+
+        let command: Command<Opts, ()> = Command::new("sample", "1.0.0")
+            .help_option(Some(Opt::new("usage", "Print usage").with_short('?')));
+        assert_eq!(
+            command.help_message().to_string(),
+            "\
+USAGE:
+    sample [OPTIONS]
+
+OPTIONS:
+    -V, --verbose    Use verbose output
+    -?, --usage      Print usage
+        --version    Print version information
+"
+        );
+    }
+
+    #[test]
+    fn format_styled() {
+        let command: Command<(), ()> =
+            Command::new("sample", "1.0.0")
+                .version_option(None)
+                .styles(Styles {
+                    heading: "<h>",
+                    literal: "<l>",
+                    error: "<e>",
+                });
+        assert_eq!(
+            command.help_message().styled(true).to_string(),
+            "\
+<h>USAGE:\x1b[m
+    sample [OPTIONS]
+
+<h>OPTIONS:\x1b[m
+    <l>-h, --help\x1b[m    Print help information
+"
+        );
+        assert_eq!(
+            command.help_message().styled(false).to_string(),
+            command.help_message().to_string()
+        );
+    }
+
+    #[test]
+    fn format_metadata() {
+        let command: Command<(), ()> = Command::new("sample", "1.0.0")
+            .about("A sample command")
+            .authors("Alice <alice@example.com>")
+            .long_description("This command does nothing.")
+            .after_help("EXAMPLES:\n    sample --help")
+            .version_option(None);
+        assert_eq!(
+            command.help_message().to_string(),
+            "\
+sample 1.0.0
+Alice <alice@example.com>
+A sample command
+
+This command does nothing.
+
+USAGE:
+    sample [OPTIONS]
+
+OPTIONS:
+    -h, --help    Print help information
+
+EXAMPLES:
+    sample --help
+"
+        );
+    }
 }
@@ -0,0 +1,292 @@
+use crate::{Arguments, Error, Options, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A source of values for options and arguments missing from the command line.
+///
+/// Keys are the long names of options and the names of arguments.
+/// A flag is set by a boolean value such as `true` or `false`.
+///
+/// Since options and arguments share the keys, a command with config sources rejects
+/// an option and an argument with the same name.
+pub trait ConfigSource: std::fmt::Debug {
+    /// Returns the values for `key`, or an empty vector if it is not set.
+    ///
+    /// Several values are used for an option given several times or the variable argument.
+    fn values(&self, key: &str) -> Vec<String>;
+
+    /// Returns the kind of this source reported in `ValueSources`.
+    fn kind(&self) -> ValueSource {
+        ValueSource::Config
+    }
+}
+
+/// Where the value of an option or an argument came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ValueSource {
+    /// Not given anywhere
+    Default,
+    Config,
+    Env,
+    CommandLine,
+}
+
+impl std::fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            ValueSource::Default => "default",
+            ValueSource::Config => "config",
+            ValueSource::Env => "env",
+            ValueSource::CommandLine => "command line",
+        };
+        f.write_str(s)
+    }
+}
+
+/// The sources of options and arguments returned by `Command::parse_with_sources`.
+///
+/// Options and arguments are kept apart, so an option and an argument may have the same name.
+///
+/// # Example
+/// ```
+/// # let sources = entrance::ValueSources::default();
+/// for (long, source) in sources.options() {
+///     println!("--{} = {}", long, source);
+/// }
+/// for (name, source) in sources.arguments() {
+///     println!("{} = {}", name, source);
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ValueSources {
+    options: HashMap<String, ValueSource>,
+    arguments: HashMap<String, ValueSource>,
+}
+
+impl ValueSources {
+    /// Returns the source of the option, or `ValueSource::Default` if not given.
+    pub fn option(&self, long: &str) -> ValueSource {
+        self.options
+            .get(long)
+            .copied()
+            .unwrap_or(ValueSource::Default)
+    }
+
+    /// Returns the source of the argument, or `ValueSource::Default` if not given.
+    pub fn argument(&self, name: &str) -> ValueSource {
+        self.arguments
+            .get(name)
+            .copied()
+            .unwrap_or(ValueSource::Default)
+    }
+
+    /// Iterates over the options given in any source with their long names.
+    pub fn options(&self) -> impl Iterator<Item = (&str, ValueSource)> {
+        self.options
+            .iter()
+            .map(|(key, &source)| (key.as_str(), source))
+    }
+
+    /// Iterates over the arguments given in any source.
+    pub fn arguments(&self) -> impl Iterator<Item = (&str, ValueSource)> {
+        self.arguments
+            .iter()
+            .map(|(key, &source)| (key.as_str(), source))
+    }
+
+    pub(crate) fn insert_option(&mut self, long: &str, source: ValueSource) {
+        self.options.insert(long.to_string(), source);
+    }
+
+    pub(crate) fn insert_argument(&mut self, name: &str, source: ValueSource) {
+        self.arguments.insert(name.to_string(), source);
+    }
+}
+
+/// A configuration file consisting of `key = value` lines.
+///
+/// Empty lines and lines starting with `#` are ignored.
+/// A value may be quoted with `"` or `'` to keep its surrounding whitespace,
+/// and a key given several times has several values.
+///
+/// # Example
+/// ```
+/// use entrance::{ConfigSource, KeyValueFile};
+///
+/// let config: KeyValueFile = "# comment\nlevel = 3\nexclude = a.txt\nexclude = 'b.txt'"
+///     .parse()?;
+/// assert_eq!(config.values("level"), ["3"]);
+/// assert_eq!(config.values("exclude"), ["a.txt", "b.txt"]);
+/// # Ok::<(), entrance::Error>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct KeyValueFile {
+    entries: Vec<(String, String)>,
+}
+
+impl KeyValueFile {
+    /// Reads and parses the file at `path`.
+    ///
+    /// # Errors
+    /// `Error::Config` is returned with the file and line at fault.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            Error::Config(
+                path.display().to_string(),
+                format!("failed to read: {}", err),
+            )
+        })?;
+        Self::parse(&content, &path.display().to_string())
+    }
+
+    fn parse(content: &str, origin: &str) -> Result<Self> {
+        let mut entries = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid =
+                |message: &str| Error::Config(format!("{}:{}", origin, i + 1), message.to_string());
+
+            let mut split = line.splitn(2, '=');
+            let key = split.next().unwrap_or_default().trim();
+            let value = split
+                .next()
+                .ok_or_else(|| invalid("expected `key = value`"))?
+                .trim();
+            if key.is_empty() {
+                return Err(invalid("missing key before `=`"));
+            }
+            entries.push((key.to_string(), unquote(value).to_string()));
+        }
+        Ok(Self { entries })
+    }
+}
+
+impl FromStr for KeyValueFile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s, "line")
+    }
+}
+
+impl ConfigSource for KeyValueFile {
+    fn values(&self, key: &str) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in &['"', '\''] {
+        if value.len() >= 2 && value.starts_with(*quote) && value.ends_with(*quote) {
+            return &value[1..value.len() - 1];
+        }
+    }
+    value
+}
+
+/// Environment variables named after keys with a prefix.
+///
+/// A key is uppercased with `-` replaced by `_`, e.g. `dry-run` is read from `APP_DRY_RUN`
+/// for the prefix `APP`.
+#[derive(Debug, Clone)]
+pub struct EnvSource {
+    prefix: String,
+}
+
+impl EnvSource {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+        }
+    }
+
+    /// Returns the name of the environment variable for `key`.
+    pub fn var_name(&self, key: &str) -> String {
+        let key = key.to_uppercase().replace('-', "_");
+        if self.prefix.is_empty() {
+            key
+        } else {
+            format!("{}_{}", self.prefix, key)
+        }
+    }
+}
+
+impl ConfigSource for EnvSource {
+    fn values(&self, key: &str) -> Vec<String> {
+        std::env::var(self.var_name(key)).into_iter().collect()
+    }
+
+    fn kind(&self) -> ValueSource {
+        ValueSource::Env
+    }
+}
+
+/// Interprets a value for a flag.
+pub(crate) fn parse_flag(key: &str, value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(Error::Config(
+            format!("`{}`", key),
+            format!("expected a boolean for a flag, found `{}`", value),
+        )),
+    }
+}
+
+/// Rejects an option and an argument with the same name, which would share a key.
+pub(crate) fn check_shared_keys<Opts: Options, Args: Arguments>() -> Result<()> {
+    let names = Args::spec()
+        .iter()
+        .map(|spec| spec.name)
+        .chain(Args::var_spec().map(|spec| spec.name));
+    for name in names {
+        if Opts::spec().iter().any(|opt| opt.long == name) {
+            return Err(Error::Config(
+                format!("`{}`", name),
+                "the key is shared by an option and an argument".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_value_file() -> Result<()> {
+        let config: KeyValueFile = "\n  # comment\nname = \" a b \"\nempty =\n".parse()?;
+        assert_eq!(config.values("name"), [" a b "]);
+        assert_eq!(config.values("empty"), [""]);
+        assert!(config.values("missing").is_empty());
+
+        let err = "a = 1\nb\n".parse::<KeyValueFile>().unwrap_err();
+        assert_eq!(err.to_string(), "line:2: expected `key = value`");
+        let err = " = 1".parse::<KeyValueFile>().unwrap_err();
+        assert_eq!(err.to_string(), "line:1: missing key before `=`");
+
+        Ok(())
+    }
+
+    #[test]
+    fn env_source() {
+        let env = EnvSource::new("ENTRANCE_TEST");
+        assert_eq!(env.var_name("dry-run"), "ENTRANCE_TEST_DRY_RUN");
+        std::env::set_var("ENTRANCE_TEST_DRY_RUN", "yes");
+        assert_eq!(env.values("dry-run"), ["yes"]);
+        assert!(env.values("missing").is_empty());
+        assert_eq!(env.kind(), ValueSource::Env);
+    }
+}
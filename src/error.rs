@@ -1,11 +1,67 @@
 use thiserror::Error;
 
+/// The exit code for a command used incorrectly, defined in `sysexits.h`.
+pub const EX_USAGE: i32 = 64;
+
+/// The exit code for an input file which does not exist or cannot be read.
+pub const EX_NOINPUT: i32 = 66;
+
+/// The exit code for an error in a configuration file.
+pub const EX_CONFIG: i32 = 78;
+
 #[derive(Error, Debug)]
 pub enum EntranceError {
     #[error("Invalid number of arguments")]
     InvalidNumberOfArguments,
+    #[error("Invalid number of values for {0}: {1}")]
+    InvalidNumberOfValues(String, String),
+    #[error("Invalid number of occurrences of {0}: {1}")]
+    InvalidNumberOfOccurrences(String, String),
     #[error("Invalid option")]
     InvalidOption,
+    #[error("Invalid value for option {0}: expected `KEY{2}VALUE`, but `{1}` given")]
+    InvalidKeyValue(String, String, char),
+    #[error("Missing value for option {0}")]
+    MissingValue(String),
+    #[error("Option {0} doesn't take a value")]
+    UnexpectedValue(String),
+    #[error("{0}: {1}")]
+    ResponseFile(String, String),
+    #[error("{0}: {1}")]
+    UnreadableFile(String, String),
+    #[error("{0}: {1}")]
+    Config(String, String),
     #[error("Failed to parse")]
     ParseError(#[source] Box<dyn std::error::Error>),
 }
+
+impl EntranceError {
+    /// Returns the exit code for this error, following `sysexits.h`.
+    ///
+    /// `EX_CONFIG` is returned for errors in config sources, `EX_NOINPUT` for files which
+    /// cannot be read, and `EX_USAGE` for the others.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            EntranceError::Config(..) => EX_CONFIG,
+            EntranceError::UnreadableFile(..) => EX_NOINPUT,
+            _ => EX_USAGE,
+        }
+    }
+}
+
+/// Checks that `count` is within `min` and `max`, describing the violation on failure.
+pub(crate) fn check_count(
+    count: usize,
+    min: usize,
+    max: Option<usize>,
+) -> std::result::Result<(), String> {
+    let expected = if count < min {
+        format!("at least {}", min)
+    } else {
+        match max {
+            Some(max) if count > max => format!("at most {}", max),
+            _ => return Ok(()),
+        }
+    };
+    Err(format!("expected {}, but {} given", expected, count))
+}
@@ -0,0 +1,364 @@
+use crate::{Error, OptionItem, Result};
+use std::collections::VecDeque;
+
+/// A command line argument classified by its leading dashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// `--name` or `--name=value`
+    Long { name: String, value: Option<String> },
+    /// `-abc`, a cluster of short options without the leading dash
+    Shorts(String),
+    /// `--`, which terminates options
+    DoubleDash,
+    /// A lone `-`, which conventionally means stdin or stdout
+    Dash,
+    /// A negative number such as `-5` or `-3.2e4`
+    Number(String),
+    /// Any other argument
+    Positional(String),
+}
+
+impl Token {
+    pub fn classify(arg: &str) -> Self {
+        if arg == "--" {
+            Token::DoubleDash
+        } else if let Some(long) = arg.strip_prefix("--") {
+            let mut split = long.splitn(2, '=');
+            let name = split.next().unwrap_or_default().to_string();
+            let value = split.next().map(str::to_string);
+            Token::Long { name, value }
+        } else if arg == "-" {
+            Token::Dash
+        } else if is_negative_number(arg) {
+            Token::Number(arg.to_string())
+        } else if let Some(shorts) = arg.strip_prefix('-') {
+            Token::Shorts(shorts.to_string())
+        } else {
+            Token::Positional(arg.to_string())
+        }
+    }
+}
+
+fn is_negative_number(arg: &str) -> bool {
+    match arg.strip_prefix('-') {
+        Some(number) => {
+            number.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+                && number.parse::<f64>().is_ok()
+        }
+        None => false,
+    }
+}
+
+/// A cursor over command line arguments for parsing options one by one.
+///
+/// Options are taken with `next_option` until it returns `None`, and their values are taken
+/// on demand with `take_value`.
+/// The remaining arguments are taken as positional ones through `Iterator`.
+///
+/// A negative number like `-5` is regarded as short options only if its first digit is
+/// registered with `with_shorts`.
+/// Otherwise, it is a positional argument or a value of an option.
+///
+/// # Example
+/// ```
+/// use entrance::{ArgCursor, OptionItem};
+///
+/// let args = ["-vo", "out.txt", "--level=3", "--", "-input-"];
+/// let mut cursor = ArgCursor::new(args.iter().map(|s| s.to_string()));
+///
+/// assert_eq!(cursor.next_option()?, Some(OptionItem::Short('v')));
+/// assert_eq!(cursor.next_option()?, Some(OptionItem::Short('o')));
+/// assert_eq!(cursor.take_value(), Some("out.txt".to_string()));
+/// assert_eq!(cursor.next_option()?, Some(OptionItem::Long("level".to_string())));
+/// assert_eq!(cursor.take_value(), Some("3".to_string()));
+/// assert_eq!(cursor.next_option()?, None);
+/// assert_eq!(cursor.next(), Some("-input-".to_string()));
+/// # Ok::<(), entrance::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct ArgCursor {
+    args: VecDeque<String>,
+    pending: Option<Pending>,
+    shorts: Vec<char>,
+}
+
+/// The rest of the argument which contains the last option.
+#[derive(Debug)]
+enum Pending {
+    /// Short options following the last short option
+    Shorts(String),
+    /// The value attached to the last long option
+    Value { option: String, value: String },
+}
+
+impl ArgCursor {
+    pub fn new<I: IntoIterator<Item = String>>(args: I) -> Self {
+        Self {
+            args: args.into_iter().collect(),
+            pending: None,
+            shorts: Vec::new(),
+        }
+    }
+
+    /// Registers short options to distinguish them from negative numbers.
+    pub fn with_shorts<I: IntoIterator<Item = char>>(mut self, shorts: I) -> Self {
+        self.shorts.extend(shorts);
+        self
+    }
+
+    /// Returns the next argument without consuming it.
+    ///
+    /// The rest of a partially consumed argument, e.g. `bc` of `-abc`, is not considered.
+    pub fn peek(&self) -> Option<&str> {
+        self.args.front().map(String::as_str)
+    }
+
+    /// Returns the classified next argument without consuming it.
+    ///
+    /// The rest of a partially consumed argument is returned first, as `next_token` does.
+    pub fn peek_token(&self) -> Option<Token> {
+        match &self.pending {
+            Some(Pending::Shorts(shorts)) => Some(Token::Shorts(shorts.clone())),
+            Some(Pending::Value { option, value }) => Some(Token::Long {
+                name: option.clone(),
+                value: Some(value.clone()),
+            }),
+            None => self.peek().map(Token::classify),
+        }
+    }
+
+    /// Consumes the next argument and classifies it.
+    ///
+    /// The rest of a partially consumed argument is returned first.
+    pub fn next_token(&mut self) -> Option<Token> {
+        match self.pending.take() {
+            Some(Pending::Shorts(shorts)) => Some(Token::Shorts(shorts)),
+            Some(Pending::Value { option, value }) => Some(Token::Long {
+                name: option,
+                value: Some(value),
+            }),
+            None => self.args.pop_front().map(|arg| Token::classify(&arg)),
+        }
+    }
+
+    /// Consumes the next option.
+    ///
+    /// `None` is returned at the first argument which is not an option.
+    /// `--` is consumed at that time, while the other arguments are left for `Iterator`.
+    ///
+    /// # Errors
+    /// `Error::UnexpectedValue` is returned if a value attached to the last option with `=`
+    /// has not been taken, and `Error::InvalidOption` for a long option without a name like
+    /// `--=value`.
+    pub fn next_option(&mut self) -> Result<Option<OptionItem>> {
+        match self.pending.take() {
+            Some(Pending::Shorts(shorts)) => return Ok(Some(self.split_shorts(shorts))),
+            Some(Pending::Value { option, .. }) => {
+                return Err(Error::UnexpectedValue(format!("--{}", option)));
+            }
+            None => {}
+        }
+
+        match self.peek_token() {
+            Some(Token::Long { name, .. }) if name.is_empty() => {
+                self.args.pop_front();
+                Err(Error::InvalidOption)
+            }
+            Some(Token::Long { name, value }) => {
+                self.args.pop_front();
+                if let Some(value) = value {
+                    self.pending = Some(Pending::Value {
+                        option: name.clone(),
+                        value,
+                    });
+                }
+                Ok(Some(OptionItem::Long(name)))
+            }
+            Some(Token::Shorts(shorts)) => {
+                self.args.pop_front();
+                Ok(Some(self.split_shorts(shorts)))
+            }
+            Some(Token::Number(number)) if self.is_short_option(&number) => {
+                self.args.pop_front();
+                Ok(Some(self.split_shorts(number[1..].to_string())))
+            }
+            Some(Token::DoubleDash) => {
+                self.args.pop_front();
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Takes a value for the last option.
+    ///
+    /// The value attached with `=` or the rest of a short option cluster, e.g. `file` of
+    /// `-ofile`, is preferred to the next argument.
+    /// The next argument is not taken if it looks like an option, while a lone `-` and
+    /// negative numbers are taken.
+    pub fn take_value(&mut self) -> Option<String> {
+        if let Some(value) = self.take_attached_value() {
+            return Some(value);
+        }
+        match self.peek_token()? {
+            Token::Long { .. } | Token::Shorts(_) | Token::DoubleDash => None,
+            Token::Number(number) if self.is_short_option(&number) => None,
+            _ => self.args.pop_front(),
+        }
+    }
+
+    /// Takes a value for the last option even if it starts with `-`.
+    pub fn take_hyphen_value(&mut self) -> Option<String> {
+        self.take_attached_value().or_else(|| self.args.pop_front())
+    }
+
+    /// Takes the value attached to the last long option with `=`, e.g. `always` of
+    /// `--color=always`, without looking at the next argument.
+    pub fn take_equals_value(&mut self) -> Option<String> {
+        match self.pending.take()? {
+            Pending::Value { value, .. } => Some(value),
+            shorts => {
+                self.pending = Some(shorts);
+                None
+            }
+        }
+    }
+
+    fn take_attached_value(&mut self) -> Option<String> {
+        match self.pending.take()? {
+            Pending::Shorts(value) | Pending::Value { value, .. } => Some(value),
+        }
+    }
+
+    fn is_short_option(&self, number: &str) -> bool {
+        number[1..]
+            .chars()
+            .next()
+            .is_some_and(|c| self.shorts.contains(&c))
+    }
+
+    fn split_shorts(&mut self, shorts: String) -> OptionItem {
+        let mut chars = shorts.chars();
+        let short = chars.next().unwrap_or('-');
+        let rest = chars.as_str();
+        if !rest.is_empty() {
+            self.pending = Some(Pending::Shorts(rest.to_string()));
+        }
+        OptionItem::Short(short)
+    }
+}
+
+/// Remaining arguments are yielded as they are.
+impl Iterator for ArgCursor {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.args.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify() {
+        let long = |name: &str, value: Option<&str>| Token::Long {
+            name: name.to_string(),
+            value: value.map(str::to_string),
+        };
+        assert_eq!(Token::classify("--verbose"), long("verbose", None));
+        assert_eq!(Token::classify("--level=3"), long("level", Some("3")));
+        assert_eq!(Token::classify("--sep=="), long("sep", Some("=")));
+        assert_eq!(Token::classify("-abc"), Token::Shorts("abc".to_string()));
+        assert_eq!(Token::classify("--"), Token::DoubleDash);
+        assert_eq!(Token::classify("-"), Token::Dash);
+        assert_eq!(Token::classify("-5"), Token::Number("-5".to_string()));
+        assert_eq!(Token::classify("-.5e3"), Token::Number("-.5e3".to_string()));
+        assert_eq!(Token::classify("-inf"), Token::Shorts("inf".to_string()));
+        assert_eq!(
+            Token::classify("file"),
+            Token::Positional("file".to_string())
+        );
+    }
+
+    #[test]
+    fn unexpected_value() -> Result<()> {
+        let args = ["--verbose=yes", "-"];
+        let mut cursor = ArgCursor::new(args.iter().map(|s| s.to_string()));
+
+        assert_eq!(
+            cursor.next_option()?,
+            Some(OptionItem::Long("verbose".to_string()))
+        );
+        assert!(matches!(
+            cursor.next_option(),
+            Err(Error::UnexpectedValue(option)) if option == "--verbose"
+        ));
+        assert_eq!(cursor.next_option()?, None);
+        assert_eq!(cursor.next(), Some("-".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn peek_pending() -> Result<()> {
+        let args = ["-abc", "--level=3", "file"];
+        let mut cursor = ArgCursor::new(args.iter().map(|s| s.to_string()));
+
+        assert_eq!(cursor.next_option()?, Some(OptionItem::Short('a')));
+        assert_eq!(cursor.peek_token(), Some(Token::Shorts("bc".to_string())));
+        assert_eq!(cursor.peek_token(), cursor.next_token());
+
+        assert_eq!(
+            cursor.next_option()?,
+            Some(OptionItem::Long("level".to_string()))
+        );
+        let level = Token::Long {
+            name: "level".to_string(),
+            value: Some("3".to_string()),
+        };
+        assert_eq!(cursor.peek_token(), Some(level.clone()));
+        assert_eq!(cursor.next_token(), Some(level));
+        assert_eq!(
+            cursor.peek_token(),
+            Some(Token::Positional("file".to_string()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn empty_long_name() {
+        let args = ["--=x", "file"];
+        let mut cursor = ArgCursor::new(args.iter().map(|s| s.to_string()));
+
+        assert!(matches!(cursor.next_option(), Err(Error::InvalidOption)));
+        assert_eq!(cursor.next(), Some("file".to_string()));
+    }
+
+    #[test]
+    fn negative_numbers() -> Result<()> {
+        let args = ["-1", "-5", "--offset", "-3.2", "--offset", "-x", "-1"];
+        let mut cursor = ArgCursor::new(args.iter().map(|s| s.to_string())).with_shorts(vec!['1']);
+
+        assert_eq!(cursor.next_option()?, Some(OptionItem::Short('1')));
+        assert_eq!(cursor.next_option()?, None);
+        assert_eq!(cursor.next(), Some("-5".to_string()));
+
+        assert_eq!(
+            cursor.next_option()?,
+            Some(OptionItem::Long("offset".to_string()))
+        );
+        assert_eq!(cursor.take_value(), Some("-3.2".to_string()));
+        assert_eq!(
+            cursor.next_option()?,
+            Some(OptionItem::Long("offset".to_string()))
+        );
+        assert_eq!(cursor.take_value(), None);
+        assert_eq!(cursor.take_hyphen_value(), Some("-x".to_string()));
+        assert_eq!(cursor.take_value(), None);
+
+        Ok(())
+    }
+}
@@ -10,11 +10,6 @@
 //!
 //! #[derive(Options)]
 //! enum Opts {
-//!     #[entrance(description = "Print the help message")]
-//!     #[entrance(short = 'h')]
-//!     #[entrance(informative(entrance::help))]
-//!     Help,
-//!
 //!     #[entrance(description = "Use verbose output")]
 //!     #[entrance(short = 'v')]
 //!     Verbose,
@@ -28,7 +23,7 @@
 //!
 //! let args = ["program", "-v", "path/to/file"].iter().map(|s| s.to_string());
 //!
-//! // Parse only options to exit immediately with "--version" or "--help".
+//! // "-h, --help" and "-V, --version" are handled by `Command` itself.
 //! let command = Command::<Opts, Args>::new("program", "1.0.0");
 //!
 //! let (opts, args) = command.parse(args).unwrap();
@@ -37,31 +32,126 @@
 //!
 //! ```
 
+// Some tests predate `matches!` and are kept as they were written.
+#![cfg_attr(test, allow(clippy::match_like_matches_macro, clippy::let_unit_value))]
+
 mod arguments;
+mod builder;
 mod command;
+mod config;
 mod error;
+mod lexer;
+mod markdown;
 mod options;
+mod response;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod spec;
+mod stdio;
+mod style;
+pub mod testing;
 
 pub use crate::arguments::*;
+pub use crate::builder::*;
 pub use crate::command::*;
+pub use crate::config::*;
 pub use crate::error::EntranceError as Error;
+pub use crate::error::{EX_CONFIG, EX_NOINPUT, EX_USAGE};
+pub use crate::lexer::*;
+pub use crate::markdown::*;
 pub use crate::options::*;
+pub use crate::response::*;
+#[cfg(feature = "serde")]
+pub use crate::serde_support::*;
+pub use crate::spec::*;
+pub use crate::stdio::*;
+pub use crate::style::{ColorChoice, Styles};
 pub use entrance_derive::*;
+#[cfg(feature = "serde")]
+pub use serde;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Creates a `Command` filled with the name, version, authors and description of the crate.
+///
+/// These values are taken from the `CARGO_PKG_*` environment variables at compile time.
+///
+/// # Example
+/// ```
+/// let command: entrance::Command<(), ()> = entrance::crate_command!();
+/// assert_eq!(command.name(), env!("CARGO_PKG_NAME"));
+/// ```
+#[macro_export]
+macro_rules! crate_command {
+    () => {
+        $crate::Command::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .authors(&env!("CARGO_PKG_AUTHORS").replace(':', ", "))
+            .about(env!("CARGO_PKG_DESCRIPTION"))
+    };
+}
+
+/// Expands to the items given, or to a compile error without the `serde` feature.
+///
+/// This is used by `#[derive(Arguments)]` with `#[entrance(deserialize)]`, since the derive
+/// macro cannot tell which features of entrance are enabled.
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __requires_serde {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+/// Expands to the items given, or to a compile error without the `serde` feature.
+///
+/// This is used by `#[derive(Arguments)]` with `#[entrance(deserialize)]`, since the derive
+/// macro cannot tell which features of entrance are enabled.
+///
+/// # Example
+/// ```compile_fail
+/// #[derive(entrance::Arguments)]
+/// #[entrance(deserialize)]
+/// struct Args {
+///     num: u32,
+/// }
+/// ```
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __requires_serde {
+    ($($item:item)*) => {
+        compile_error!("`#[entrance(deserialize)]` requires the `serde` feature of entrance");
+    };
+}
+
 ///
 /// A callback function to print help messages
 ///
 pub fn help<O: Options, A: Arguments>(command: &Command<O, A>) {
-    println!("{}", command.help_message());
+    print_output(format_args!("{}\n", command.styled_help_message()));
 }
 
 ///
 /// A callback function to print the version
 ///
 pub fn version<O: Options, A: Arguments>(command: &Command<O, A>) {
-    println!("{} {}", command.name(), command.version());
+    print_output(format_args!("{} {}\n", command.name(), command.version()));
+}
+
+///
+/// A callback function to print the spec of the command as JSON
+///
+pub fn json_spec<O: Options, A: Arguments>(command: &Command<O, A>) {
+    print_output(command.json_spec());
+}
+
+/// Prints the output of an informative option to stdout.
+///
+/// `entrance::testing::TryParseFrom::try_parse_from` captures it instead,
+/// so callbacks of informative options should print with this to be tested.
+pub fn print_output<T: std::fmt::Display>(output: T) {
+    testing::print_output(&output)
 }
 
 /// A helper function to parse argument
@@ -84,6 +174,63 @@ where
         .collect()
 }
 
+/// Parses values like `KEY=VALUE` of the option described by `spec` into a map.
+///
+/// This is used by `#[derive(Options)]` for variants of maps.
+pub fn parse_key_values<K, V, I, M>(spec: &Opt, values: I) -> Result<M>
+where
+    K: std::str::FromStr,
+    K::Err: std::error::Error + 'static,
+    V: std::str::FromStr,
+    V::Err: std::error::Error + 'static,
+    I: Iterator<Item = String>,
+    M: std::iter::FromIterator<(K, V)>,
+{
+    values
+        .map(|value| {
+            let (key, value) = spec.split_key_value(&value)?;
+            Ok((
+                parse_argument(key.to_string())?,
+                parse_argument(value.to_string())?,
+            ))
+        })
+        .collect()
+}
+
+/// Parses the next `N` arguments into an array.
+///
+/// This is used by `#[derive(Arguments)]` for fields of arrays.
+pub fn parse_array<T, E, I, const N: usize>(args: &mut I) -> Result<[T; N]>
+where
+    T: std::str::FromStr<Err = E>,
+    E: std::error::Error + 'static,
+    I: Iterator<Item = String>,
+{
+    let values: Vec<T> = parse_variable_argument(args.by_ref().take(N))?;
+    std::convert::TryInto::try_into(values).map_err(|_| Error::InvalidNumberOfArguments)
+}
+
+/// Splits the remaining arguments into the values of the variable argument described by `spec`
+/// and the last `after` values for the fields following it.
+///
+/// This is used by `#[derive(Arguments)]`.
+pub fn split_variable_argument<I>(
+    args: &mut I,
+    after: usize,
+    spec: &Arg,
+) -> Result<(Vec<String>, std::vec::IntoIter<String>)>
+where
+    I: Iterator<Item = String>,
+{
+    let mut values: Vec<String> = args.collect();
+    if values.len() < after {
+        return Err(Error::InvalidNumberOfArguments);
+    }
+    let rest = values.split_off(values.len() - after);
+    spec.check_values(values.len())?;
+    Ok((values, rest.into_iter()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,6 +250,49 @@ mod tests {
         assert!(is_parse_error);
     }
 
+    #[test]
+    fn test_parse_array() {
+        let mut args = vec!["1", "2", "3"].into_iter().map(String::from);
+        let parsed: [u8; 2] = parse_array(&mut args).unwrap();
+        assert_eq!(parsed, [1, 2]);
+        assert_eq!(args.next(), Some("3".to_string()));
+
+        let parsed: Result<[u8; 2]> = parse_array(&mut args);
+        assert!(matches!(parsed, Err(Error::InvalidNumberOfArguments)));
+    }
+
+    #[test]
+    fn test_parse_key_values() {
+        let spec = Opt::new("define", "").with_separator('=');
+        let values = vec!["a=1", "b=2"].into_iter().map(String::from);
+        let parsed: std::collections::BTreeMap<String, u32> =
+            parse_key_values(&spec, values).unwrap();
+        assert_eq!(parsed["a"], 1);
+        assert_eq!(parsed["b"], 2);
+
+        let values = vec!["a"].into_iter().map(String::from);
+        let parsed: Result<Vec<(String, u32)>> = parse_key_values(&spec, values);
+        assert!(matches!(parsed, Err(Error::InvalidKeyValue(..))));
+    }
+
+    #[test]
+    fn test_split_variable_argument() {
+        let spec = Arg::new("src", "").with_min_values(1);
+        let split = |args: &[&str]| {
+            let mut args = args.iter().map(|s| s.to_string());
+            split_variable_argument(&mut args, 1, &spec)
+                .map(|(values, rest)| (values, rest.collect::<Vec<_>>()))
+        };
+        let (values, rest) = split(&["a", "b", "c"]).unwrap();
+        assert_eq!(values, ["a", "b"]);
+        assert_eq!(rest, ["c"]);
+        assert!(matches!(
+            split(&["c"]),
+            Err(Error::InvalidNumberOfValues(..))
+        ));
+        assert!(matches!(split(&[]), Err(Error::InvalidNumberOfArguments)));
+    }
+
     #[test]
     fn test_parse_variable_argument() {
         let args = vec!["1.0", "2.0", "3.0"].into_iter().map(String::from);
@@ -0,0 +1,151 @@
+use crate::HelpDisplay;
+use std::fmt;
+
+/// Helper struct for rendering a command reference as Markdown with `format!` and `{}`.
+///
+/// The output consists of a heading with the name and version, the descriptions,
+/// a usage code block and tables of options and arguments.
+/// It depends only on the command, so it is suitable for snapshot testing.
+#[derive(Debug)]
+pub struct MarkdownDisplay<'a> {
+    help: HelpDisplay<'a>,
+}
+
+impl<'a> MarkdownDisplay<'a> {
+    pub(crate) fn new(help: HelpDisplay<'a>) -> Self {
+        Self { help }
+    }
+}
+
+impl<'a> fmt::Display for MarkdownDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let help = &self.help;
+        let info = help.info;
+
+        writeln!(f, "# {}", info.version_message())?;
+        for text in info
+            .about
+            .iter()
+            .chain(&info.long_description)
+            .chain(&info.authors)
+        {
+            writeln!(f)?;
+            writeln!(f, "{}", text)?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "## Usage")?;
+        writeln!(f)?;
+        writeln!(f, "```text")?;
+        writeln!(f, "{}", help.usage())?;
+        writeln!(f, "```")?;
+
+        if !help.options.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "## Options")?;
+            writeln!(f)?;
+            writeln!(f, "| Option | Description |")?;
+            writeln!(f, "| --- | --- |")?;
+            for opt in &help.options {
+                write!(f, "| ")?;
+                if let Some(short) = opt.short {
+                    write!(f, "`-{}`, ", short)?;
+                }
+                write!(f, "`--{}", opt.long_usage())?;
+                let description = opt.description_with_aliases();
+                writeln!(f, "` | {} |", TableCell(&description))?;
+            }
+        }
+
+        if !help.arguments.is_empty() || help.variable_argument.is_some() {
+            writeln!(f)?;
+            writeln!(f, "## Arguments")?;
+            writeln!(f)?;
+            writeln!(f, "| Argument | Description |")?;
+            writeln!(f, "| --- | --- |")?;
+            for (arg, variadic) in help.positionals() {
+                let ellipsis = if variadic { "..." } else { "" };
+                let description = TableCell(arg.description);
+                writeln!(f, "| `{}{}` | {} |", arg.name, ellipsis, description)?;
+            }
+        }
+
+        if let Some(text) = &info.after_help {
+            writeln!(f)?;
+            writeln!(f, "{}", text)?;
+        }
+        Ok(())
+    }
+}
+
+/// A text escaped to fit in a cell of a Markdown table.
+struct TableCell<'a>(&'a str);
+
+impl<'a> fmt::Display for TableCell<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines = self.0.lines();
+        if let Some(line) = lines.next() {
+            f.write_str(&line.replace('|', "\\|"))?;
+        }
+        for line in lines {
+            write!(f, "<br>{}", line.replace('|', "\\|"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Arg, CommandBuilder, Opt};
+
+    #[test]
+    fn markdown() {
+        let command = CommandBuilder::new("sample", "1.0.0")
+            .about("A sample command")
+            .after_help("See also `other`.")
+            .version_option(None)
+            .option(Opt::new("verbose", "Use verbose output").with_short('v'))
+            .option(Opt::new("format", "Output format: a | b").with_value())
+            .argument(Arg::new("num", "The number of lines"))
+            .variable_argument(Arg::new("files", "Paths to files\nor `-` for stdin"));
+        assert_eq!(
+            command.markdown().to_string(),
+            "\
+# sample 1.0.0
+
+A sample command
+
+## Usage
+
+```text
+sample [OPTIONS] <num> [files]...
+```
+
+## Options
+
+| Option | Description |
+| --- | --- |
+| `-v`, `--verbose` | Use verbose output |
+| `--format <VALUE>` | Output format: a \\| b |
+| `-h`, `--help` | Print help information |
+
+## Arguments
+
+| Argument | Description |
+| --- | --- |
+| `num` | The number of lines |
+| `files...` | Paths to files<br>or `-` for stdin |
+
+See also `other`.
+"
+        );
+
+        let command = CommandBuilder::new("empty", "0.1.0")
+            .help_option(None)
+            .version_option(None);
+        assert_eq!(
+            command.markdown().to_string(),
+            "# empty 0.1.0\n\n## Usage\n\n```text\nempty\n```\n"
+        );
+    }
+}
@@ -1,12 +1,33 @@
-use crate::{Arguments, Command, Error, Result};
+use crate::error::check_count;
+use crate::{ArgCursor, Arguments, Command, Error, Result};
+use std::fmt;
 
+/// An option name given in command line arguments.
 #[derive(Debug, PartialEq, Eq)]
-#[doc(hidden)]
 pub enum OptionItem {
     Long(String),
     Short(char),
 }
 
+impl OptionItem {
+    pub(crate) fn matches(&self, opt: &Opt) -> bool {
+        match self {
+            OptionItem::Long(long) => opt.has_long(long),
+            OptionItem::Short(short) => opt.has_short(*short),
+        }
+    }
+}
+
+/// Shows the option as given, e.g. `--verbose` or `-v`.
+impl fmt::Display for OptionItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionItem::Long(long) => write!(f, "--{}", long),
+            OptionItem::Short(short) => write!(f, "-{}", short),
+        }
+    }
+}
+
 /// A trait for parsing and containing options.
 ///
 /// # Example
@@ -23,24 +44,80 @@ pub enum OptionItem {
 ///     #[entrance(description = "Print version infomation")]
 ///     #[entrance(informative(entrance::version))]
 ///     Version,
+///
+///     #[entrance(description = "Write outputs to a file")]
+///     #[entrance(short = 'o')]
+///     Output(std::path::PathBuf),
 /// }
 /// ```
 ///
+/// The long name of each option is the lowercased variant name unless
+/// `#[entrance(long = "...")]` is given.
+/// `#[entrance(alias = "...")]` and `#[entrance(short_alias = 'c')]` add names accepted
+/// silently, and `visible_alias` and `visible_short_alias` add ones shown in the help message.
+/// Duplicated long names or short characters including aliases are rejected at compile time.
+///
+/// A variant with a single field takes a value, e.g. `--output file`, `--output=file`,
+/// `-o file` or `-ofile`.
+/// A value starting with `-` is accepted only if it is a negative number or
+/// `#[entrance(allow_hyphen_values)]` is given.
+///
+/// A variant with a collection like `Include(Vec<PathBuf>)` or `Tag(HashSet<String>)` collects
+/// the values of all its occurrences into one, and `#[entrance(delimiter = ',')]` splits each
+/// value into several ones, e.g. `--features a,b`.
+/// `#[entrance(min = 1)]` and `#[entrance(max = 1)]` bound the number of occurrences of
+/// any option.
+///
+/// A variant with a map like `Define(HashMap<String, String>)` or `Env(Vec<(String, u32)>)`
+/// takes pairs like `-D NAME=VALUE`, and `#[entrance(separator = ':')]` changes the separator.
+///
+/// A variant with an optional value like `Color(Option<When>)` takes a value only if it is
+/// attached with `=`, e.g. `--color=always`, and `#[entrance(implicit_value = "auto")]` is used
+/// for `--color` alone instead of `None`.
+///
 /// # Limitation
-/// The derive macro for `Options` supports only an Enum whose variants don't have any field.
+/// The derive macro for `Options` supports only an Enum whose variants are one of:
+/// - a unit variant
+/// - a single unnamed field implementing `FromStr`
+/// - a collection of such values
+/// - a map of such values
+/// - an `Option` of such a value
 pub trait Options: Sized {
-    fn parse(option: OptionItem) -> Result<Self>;
+    /// Parses an option, taking its value from `cursor` if necessary.
+    fn parse(option: OptionItem, cursor: &mut ArgCursor) -> Result<Self>;
 
     fn is_informative(&self) -> bool;
 
     fn trigger_informative<Args: Arguments>(&self, command: &Command<Self, Args>);
 
     /// This associated function is for `HelpDisplay`.
-    fn spec() -> &'static [Opt];
+    fn spec() -> &'static [Opt<'static>];
+
+    /// Merges `other` into `self` if both are the same option taking a collection.
+    ///
+    /// `other` is returned back if it is not merged.
+    fn merge(&mut self, other: Self) -> std::result::Result<(), Self> {
+        Err(other)
+    }
+}
+
+/// Merges the options taking collections given several times into their first occurrences.
+pub(crate) fn merge_options<Opts: Options>(opts: Vec<Opts>) -> Vec<Opts> {
+    let mut merged: Vec<Opts> = Vec::new();
+    'outer: for mut opt in opts {
+        for prev in merged.iter_mut() {
+            match prev.merge(opt) {
+                Ok(()) => continue 'outer,
+                Err(other) => opt = other,
+            }
+        }
+        merged.push(opt);
+    }
+    merged
 }
 
 impl Options for () {
-    fn parse(_: OptionItem) -> Result<Self> {
+    fn parse(_: OptionItem, _: &mut ArgCursor) -> Result<Self> {
         Err(Error::InvalidOption)
     }
 
@@ -52,16 +129,321 @@ impl Options for () {
         unimplemented!()
     }
 
-    fn spec() -> &'static [Opt] {
+    fn spec() -> &'static [Opt<'static>] {
         &[]
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct Opt {
-    pub long: &'static str,
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Opt<'a> {
+    pub long: &'a str,
     pub short: Option<char>,
-    pub description: &'static str,
+    pub description: &'a str,
+    pub value: ValueKind,
+    /// Whether the value can start with `-`, e.g. `--pattern -foo`.
+    pub allow_hyphen_values: bool,
+    /// A placeholder of the value shown in the help message, e.g. `PATH` of `--output <PATH>`.
+    ///
+    /// `VALUE` is shown if it is `None`.
+    pub value_name: Option<&'a str>,
+    /// Whether the option is omitted from the help message except the one of `--help-all`.
+    pub hidden: bool,
+    /// A message warned when the option is given, e.g. `use --color instead`.
+    pub deprecated: Option<&'a str>,
+    /// Long names accepted as this option without being shown in the help message
+    pub aliases: &'a [&'a str],
+    /// Long names accepted as this option and shown in the help message
+    pub visible_aliases: &'a [&'a str],
+    /// Short characters accepted as this option without being shown in the help message
+    pub short_aliases: &'a [char],
+    /// Short characters accepted as this option and shown in the help message
+    pub visible_short_aliases: &'a [char],
+    /// Long names accepted as this option with a warning to use the long name instead
+    pub deprecated_aliases: &'a [&'a str],
+    /// Short characters accepted as this option with a warning to use the long name instead
+    pub deprecated_short_aliases: &'a [char],
+    /// A character splitting a value into several ones, e.g. `,` for `--features a,b`
+    pub delimiter: Option<char>,
+    /// The minimum number of occurrences
+    pub min_occurrences: usize,
+    /// The maximum number of occurrences, or `None` for no limit
+    pub max_occurrences: Option<usize>,
+    /// A character between a key and a value for an option taking pairs, e.g. `=` for `-D a=b`
+    pub separator: Option<char>,
+    /// The value used if an option taking an optional value is given without a value
+    pub implicit_value: Option<&'a str>,
+}
+
+impl<'a> Opt<'a> {
+    pub const fn new(long: &'a str, description: &'a str) -> Self {
+        Self {
+            long,
+            short: None,
+            description,
+            value: ValueKind::Flag,
+            allow_hyphen_values: false,
+            value_name: None,
+            hidden: false,
+            deprecated: None,
+            aliases: &[],
+            visible_aliases: &[],
+            short_aliases: &[],
+            visible_short_aliases: &[],
+            deprecated_aliases: &[],
+            deprecated_short_aliases: &[],
+            delimiter: None,
+            min_occurrences: 0,
+            max_occurrences: None,
+            separator: None,
+            implicit_value: None,
+        }
+    }
+
+    pub const fn with_short(mut self, short: char) -> Self {
+        self.short = Some(short);
+        self
+    }
+
+    pub const fn with_value(mut self) -> Self {
+        self.value = ValueKind::Required;
+        self
+    }
+
+    pub const fn with_hyphen_values(mut self) -> Self {
+        self.allow_hyphen_values = true;
+        self
+    }
+
+    /// Makes the option take a value only if it is attached with `=`, e.g. `--color=always`.
+    pub const fn with_optional_value(mut self) -> Self {
+        self.value = ValueKind::Optional;
+        self
+    }
+
+    /// Makes the option take an optional value, using `value` if it is given alone.
+    pub const fn with_implicit_value(mut self, value: &'a str) -> Self {
+        self.value = ValueKind::Optional;
+        self.implicit_value = Some(value);
+        self
+    }
+
+    /// Makes the option take a value shown as `value_name` in the help message.
+    pub const fn with_value_name(mut self, value_name: &'a str) -> Self {
+        if let ValueKind::Flag = self.value {
+            self.value = ValueKind::Required;
+        }
+        self.value_name = Some(value_name);
+        self
+    }
+
+    /// Hides the option from the help message.
+    pub const fn with_hidden(mut self) -> Self {
+        self.hidden = true;
+        self
+    }
+
+    /// Marks the option as deprecated, warning `message` when it is given.
+    pub const fn with_deprecation(mut self, message: &'a str) -> Self {
+        self.deprecated = Some(message);
+        self
+    }
+
+    /// Adds long names accepted silently.
+    pub const fn with_aliases(mut self, aliases: &'a [&'a str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Adds long names shown in the help message.
+    pub const fn with_visible_aliases(mut self, aliases: &'a [&'a str]) -> Self {
+        self.visible_aliases = aliases;
+        self
+    }
+
+    /// Adds short characters accepted silently.
+    pub const fn with_short_aliases(mut self, aliases: &'a [char]) -> Self {
+        self.short_aliases = aliases;
+        self
+    }
+
+    /// Adds short characters shown in the help message.
+    pub const fn with_visible_short_aliases(mut self, aliases: &'a [char]) -> Self {
+        self.visible_short_aliases = aliases;
+        self
+    }
+
+    /// Adds long names accepted with a warning, e.g. old spellings being removed.
+    pub const fn with_deprecated_aliases(mut self, aliases: &'a [&'a str]) -> Self {
+        self.deprecated_aliases = aliases;
+        self
+    }
+
+    /// Adds short characters accepted with a warning.
+    pub const fn with_deprecated_short_aliases(mut self, aliases: &'a [char]) -> Self {
+        self.deprecated_short_aliases = aliases;
+        self
+    }
+
+    /// Makes the option take values split by `delimiter`.
+    pub const fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.value = ValueKind::Required;
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Makes the option take pairs of a key and a value separated by `separator`.
+    pub const fn with_separator(mut self, separator: char) -> Self {
+        self.value = ValueKind::Required;
+        self.separator = Some(separator);
+        self
+    }
+
+    pub const fn with_min_occurrences(mut self, min: usize) -> Self {
+        self.min_occurrences = min;
+        self
+    }
+
+    pub const fn with_max_occurrences(mut self, max: usize) -> Self {
+        self.max_occurrences = Some(max);
+        self
+    }
+
+    /// Checks that the number of occurrences of this option is within the bounds.
+    pub fn check_occurrences(&self, count: usize) -> Result<()> {
+        check_count(count, self.min_occurrences, self.max_occurrences).map_err(|message| {
+            Error::InvalidNumberOfOccurrences(format!("--{}", self.long), message)
+        })
+    }
+
+    /// Returns `true` if `long` is the long name or one of the aliases.
+    pub fn has_long(&self, long: &str) -> bool {
+        self.long == long
+            || self.aliases.contains(&long)
+            || self.visible_aliases.contains(&long)
+            || self.deprecated_aliases.contains(&long)
+    }
+
+    /// Returns `true` if `short` is the short character or one of the short aliases.
+    pub fn has_short(&self, short: char) -> bool {
+        self.shorts().any(|c| c == short)
+    }
+
+    /// Returns the short character followed by the short aliases.
+    pub fn shorts(&self) -> impl Iterator<Item = char> + 'a {
+        self.short
+            .into_iter()
+            .chain(self.short_aliases.iter().copied())
+            .chain(self.visible_short_aliases.iter().copied())
+            .chain(self.deprecated_short_aliases.iter().copied())
+    }
+
+    /// Returns the aliases shown in the help message with their prefixes, e.g. `--colour`.
+    pub(crate) fn visible_alias_names(&self) -> Vec<String> {
+        let longs = self
+            .visible_aliases
+            .iter()
+            .map(|long| format!("--{}", long));
+        let shorts = self
+            .visible_short_aliases
+            .iter()
+            .map(|short| format!("-{}", short));
+        longs.chain(shorts).collect()
+    }
+
+    /// Returns the description followed by the visible aliases, e.g. `Use colors [aliases: --colour]`.
+    pub(crate) fn description_with_aliases(&self) -> String {
+        let aliases = self.visible_alias_names();
+        if aliases.is_empty() {
+            self.description.to_string()
+        } else if self.description.is_empty() {
+            format!("[aliases: {}]", aliases.join(", "))
+        } else {
+            format!("{} [aliases: {}]", self.description, aliases.join(", "))
+        }
+    }
+
+    /// Returns the warning for the option given as `given` if the option or the alias is
+    /// deprecated, or `None` otherwise.
+    pub fn deprecation_warning(&self, given: &OptionItem) -> Option<String> {
+        let deprecated_alias = match given {
+            OptionItem::Long(long) => self.deprecated_aliases.contains(&long.as_str()),
+            OptionItem::Short(short) => self.deprecated_short_aliases.contains(short),
+        };
+        let message = match self.deprecated {
+            Some(message) => message.to_string(),
+            None if deprecated_alias => format!("use `--{}` instead", self.long),
+            None => return None,
+        };
+        Some(format!("option `{}` is deprecated: {}", given, message))
+    }
+
+    /// Returns the placeholder of the value, or `None` for a flag.
+    pub fn placeholder(&self) -> Option<&'a str> {
+        match self.value {
+            ValueKind::Flag => None,
+            ValueKind::Required | ValueKind::Optional => Some(self.value_name.unwrap_or("VALUE")),
+        }
+    }
+
+    /// Returns the long name with the placeholder shown in the help message,
+    /// e.g. `output <FILE>` or `color[=<WHEN>]`.
+    pub fn long_usage(&self) -> String {
+        match (self.value, self.placeholder()) {
+            (ValueKind::Optional, Some(placeholder)) => {
+                format!("{}[=<{}>]", self.long, placeholder)
+            }
+            (_, Some(placeholder)) => format!("{} <{}>", self.long, placeholder),
+            (_, None) => self.long.to_string(),
+        }
+    }
+
+    /// Takes the optional value of this option attached with `=` from `cursor`,
+    /// falling back to the implicit value.
+    pub fn take_optional_value(&self, cursor: &mut ArgCursor) -> Option<String> {
+        cursor
+            .take_equals_value()
+            .or_else(|| self.implicit_value.map(String::from))
+    }
+
+    /// Takes the value of this option from `cursor`.
+    pub fn take_value(&self, cursor: &mut ArgCursor) -> Result<String> {
+        let value = if self.allow_hyphen_values {
+            cursor.take_hyphen_value()
+        } else {
+            cursor.take_value()
+        };
+        value.ok_or_else(|| Error::MissingValue(format!("--{}", self.long)))
+    }
+
+    /// Takes the value of this option from `cursor`, split by the delimiter if any.
+    pub fn take_values(&self, cursor: &mut ArgCursor) -> Result<Vec<String>> {
+        let value = self.take_value(cursor)?;
+        Ok(match self.delimiter {
+            Some(delimiter) => value.split(delimiter).map(String::from).collect(),
+            None => vec![value],
+        })
+    }
+
+    /// Splits `value` into a key and a value at the first separator, `=` by default.
+    pub fn split_key_value<'v>(&self, value: &'v str) -> Result<(&'v str, &'v str)> {
+        let separator = self.separator.unwrap_or('=');
+        value.split_once(separator).ok_or_else(|| {
+            Error::InvalidKeyValue(format!("--{}", self.long), value.to_string(), separator)
+        })
+    }
+}
+
+/// Whether an option takes a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ValueKind {
+    /// The option takes no value.
+    Flag,
+    /// The option requires a value.
+    Required,
+    /// The option takes a value only if it is attached with `=`.
+    Optional,
 }
 
 #[cfg(test)]
@@ -76,7 +458,7 @@ mod tests {
         ];
 
         for option in options {
-            let option = <() as Options>::parse(option);
+            let option = <() as Options>::parse(option, &mut ArgCursor::default());
             assert!(option.is_err());
             let is_invalid_option = match option.unwrap_err() {
                 Error::InvalidOption => true,
@@ -88,6 +470,123 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn aliases() {
+        let opt = Opt::new("color", "Use colors")
+            .with_short('c')
+            .with_aliases(&["colour"])
+            .with_visible_aliases(&["colors"])
+            .with_short_aliases(&['C'])
+            .with_visible_short_aliases(&['k'])
+            .with_deprecated_aliases(&["colr"])
+            .with_deprecated_short_aliases(&['o']);
+        assert!(OptionItem::Long("colour".to_string()).matches(&opt));
+        assert!(OptionItem::Short('C').matches(&opt));
+        assert!(OptionItem::Long("colr".to_string()).matches(&opt));
+        assert!(!OptionItem::Long("clr".to_string()).matches(&opt));
+        assert_eq!(opt.shorts().collect::<Vec<_>>(), ['c', 'C', 'k', 'o']);
+        assert_eq!(
+            opt.description_with_aliases(),
+            "Use colors [aliases: --colors, -k]"
+        );
+    }
+
+    #[test]
+    fn take_values() -> Result<()> {
+        let args = ["a,b", "c"].iter().map(|s| s.to_string());
+        let mut cursor = ArgCursor::new(args);
+        let opt = Opt::new("features", "").with_delimiter(',');
+        assert_eq!(opt.take_values(&mut cursor)?, ["a", "b"]);
+        assert_eq!(opt.with_value().take_values(&mut cursor)?, ["c"]);
+        Ok(())
+    }
+
+    #[test]
+    fn split_key_value() {
+        let opt = Opt::new("define", "").with_separator('=');
+        assert_eq!(opt.split_key_value("a=b=c").unwrap(), ("a", "b=c"));
+        assert_eq!(
+            opt.with_separator(':')
+                .split_key_value("a=b")
+                .unwrap_err()
+                .to_string(),
+            "Invalid value for option --define: expected `KEY:VALUE`, but `a=b` given"
+        );
+    }
+
+    #[test]
+    fn optional_value() -> Result<()> {
+        let args = ["--color=always", "--color", "never"]
+            .iter()
+            .map(|s| s.to_string());
+        let mut cursor = ArgCursor::new(args);
+        let opt = Opt::new("color", "")
+            .with_implicit_value("auto")
+            .with_value_name("WHEN");
+        assert_eq!(opt.long_usage(), "color[=<WHEN>]");
+
+        cursor.next_option()?;
+        assert_eq!(
+            opt.take_optional_value(&mut cursor),
+            Some("always".to_string())
+        );
+        cursor.next_option()?;
+        assert_eq!(
+            opt.take_optional_value(&mut cursor),
+            Some("auto".to_string())
+        );
+        assert_eq!(
+            opt.with_optional_value().take_optional_value(&mut cursor),
+            Some("auto".to_string())
+        );
+        assert_eq!(cursor.next(), Some("never".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn check_occurrences() {
+        let opt = Opt::new("include", "").with_min_occurrences(1);
+        assert!(opt.check_occurrences(3).is_ok());
+        assert_eq!(
+            opt.check_occurrences(0).unwrap_err().to_string(),
+            "Invalid number of occurrences of --include: expected at least 1, but 0 given"
+        );
+    }
+
+    #[test]
+    fn deprecation_warning() {
+        let given = OptionItem::Long("colour".to_string());
+        let opt = Opt::new("colour", "");
+        assert_eq!(opt.deprecation_warning(&given), None);
+        let opt = opt
+            .with_short_aliases(&['U'])
+            .with_deprecation("use --color instead");
+        assert_eq!(
+            opt.deprecation_warning(&given).unwrap(),
+            "option `--colour` is deprecated: use --color instead"
+        );
+        assert_eq!(
+            opt.deprecation_warning(&OptionItem::Short('U')).unwrap(),
+            "option `-U` is deprecated: use --color instead"
+        );
+
+        let opt = Opt::new("color", "")
+            .with_deprecated_aliases(&["colour"])
+            .with_deprecated_short_aliases(&['C']);
+        assert_eq!(
+            opt.deprecation_warning(&OptionItem::Long("color".to_string())),
+            None
+        );
+        assert_eq!(
+            opt.deprecation_warning(&given).unwrap(),
+            "option `--colour` is deprecated: use `--color` instead"
+        );
+        assert_eq!(
+            opt.deprecation_warning(&OptionItem::Short('C')).unwrap(),
+            "option `-C` is deprecated: use `--color` instead"
+        );
+    }
+
     #[test]
     fn spec() {
         assert_eq!(<() as Options>::spec().len(), 0);
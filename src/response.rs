@@ -0,0 +1,259 @@
+use crate::{Error, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Expands response files, i.e. arguments of the form `@path`, into their contents.
+///
+/// A response file contains arguments separated by whitespace.
+/// Single and double quotes group words containing whitespace, a backslash escapes the next
+/// character except in single quotes, and a `#` at the start of a word begins a comment until
+/// the end of the line.
+/// Response files may refer to other response files, whose relative paths are resolved against
+/// the current directory.
+/// Arguments after `--` are left as they are.
+///
+/// # Errors
+/// `Error::UnreadableFile` is returned if a file cannot be read, and `Error::ResponseFile`
+/// with the file and line at fault if a quote is not closed or a file includes itself.
+///
+/// # Example
+/// ```
+/// let args = ["-v", "--", "@literal"].iter().map(|s| s.to_string());
+/// let args = entrance::expand_response_files(args)?;
+/// assert_eq!(args, ["-v", "--", "@literal"]);
+/// # Ok::<(), entrance::Error>(())
+/// ```
+pub fn expand_response_files<I: IntoIterator<Item = String>>(args: I) -> Result<Vec<String>> {
+    let mut expander = Expander::default();
+    for arg in args {
+        if expander.done {
+            expander.expanded.push(arg);
+            continue;
+        }
+        match arg.strip_prefix('@') {
+            Some(path) if !path.is_empty() => expander.expand(Path::new(path), &arg)?,
+            _ => expander.push(arg),
+        }
+    }
+    Ok(expander.expanded)
+}
+
+#[derive(Default)]
+struct Expander {
+    expanded: Vec<String>,
+    /// The response files being expanded, to detect cycles
+    stack: Vec<PathBuf>,
+    /// Set after `--`
+    done: bool,
+}
+
+impl Expander {
+    fn push(&mut self, arg: String) {
+        self.done = arg == "--";
+        self.expanded.push(arg);
+    }
+
+    /// Expands the response file at `path` referred to at `location`.
+    fn expand(&mut self, path: &Path, location: &str) -> Result<()> {
+        let error = |message: String| Error::ResponseFile(location.to_string(), message);
+
+        let content = fs::read_to_string(path).map_err(|err| {
+            Error::UnreadableFile(
+                location.to_string(),
+                format!("failed to read `{}`: {}", path.display(), err),
+            )
+        })?;
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if self.stack.contains(&canonical) {
+            return Err(error(format!(
+                "response file `{}` includes itself",
+                path.display()
+            )));
+        }
+
+        let words = split_words(&content).map_err(|(line, message)| {
+            Error::ResponseFile(format!("{}:{}", path.display(), line), message)
+        })?;
+        self.stack.push(canonical);
+        for (word, line) in words {
+            if self.done {
+                self.expanded.push(word);
+                continue;
+            }
+            match word.strip_prefix('@') {
+                Some(nested) if !nested.is_empty() => {
+                    let location = format!("{}:{}", path.display(), line);
+                    self.expand(Path::new(nested), &location)?;
+                }
+                _ => self.push(word),
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+/// Splits the content of a response file into words paired with their line numbers.
+///
+/// The line number of an unclosed quote is returned on error.
+fn split_words(content: &str) -> std::result::Result<Vec<(String, usize)>, (usize, String)> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut line = 1;
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | ' ' | '\t' | '\r' => {
+                if let Some(word) = word.take() {
+                    words.push((word, line));
+                }
+                if c == '\n' {
+                    line += 1;
+                }
+            }
+            '#' if word.is_none() => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            '\'' | '"' => {
+                let start = line;
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some('\\') if c == '"' => match chars.next() {
+                            Some(escaped @ ('"' | '\\')) => current.push(escaped),
+                            Some(other) => {
+                                current.push('\\');
+                                current.push(other);
+                                if other == '\n' {
+                                    line += 1;
+                                }
+                            }
+                            None => return Err((start, format!("unterminated {} quote", c))),
+                        },
+                        Some(other) => {
+                            current.push(other);
+                            if other == '\n' {
+                                line += 1;
+                            }
+                        }
+                        None => return Err((start, format!("unterminated {} quote", c))),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                // A backslash at the end of a line continues the word on the next line.
+                Some('\n') => line += 1,
+                Some(escaped) => word.get_or_insert_with(String::new).push(escaped),
+                None => {}
+            },
+            _ => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(word) = word {
+        words.push((word, line));
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("entrance-response-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn expand(args: &[&str]) -> Result<Vec<String>> {
+        expand_response_files(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn quoting() {
+        let words = |content| {
+            split_words(content)
+                .unwrap()
+                .into_iter()
+                .map(|(word, _)| word)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(words("a  b\n\tc"), ["a", "b", "c"]);
+        assert_eq!(words("'a b' \"c \\\" d\""), ["a b", "c \" d"]);
+        assert_eq!(words("a\\ b 'c\\d' x''"), ["a b", "c\\d", "x"]);
+        assert_eq!(words("'' # comment\nd#e"), ["", "d#e"]);
+        assert_eq!(
+            split_words("a\n\n'b\nc"),
+            Err((3, "unterminated ' quote".to_string()))
+        );
+        assert_eq!(
+            split_words("\"abc\\"),
+            Err((1, "unterminated \" quote".to_string()))
+        );
+    }
+
+    #[test]
+    fn nested() -> Result<()> {
+        let inner = temp_file("inner", "--level 3\n'a file.txt'");
+        let outer = temp_file(
+            "outer",
+            &format!("# options\n-v @{}\n-- @literal", inner.display()),
+        );
+
+        let args = expand(&["@", &format!("@{}", outer.display()), "b.txt"])?;
+        assert_eq!(
+            args,
+            [
+                "@",
+                "-v",
+                "--level",
+                "3",
+                "a file.txt",
+                "--",
+                "@literal",
+                "b.txt"
+            ]
+        );
+
+        fs::remove_file(inner).unwrap();
+        fs::remove_file(outer).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let cyclic = temp_file("cyclic", "");
+        fs::write(&cyclic, format!("-v\n@{}", cyclic.display())).unwrap();
+        let unclosed = temp_file("unclosed", "-v\n\"a");
+
+        let message = |args: &[&str]| expand(args).unwrap_err().to_string();
+        assert_eq!(
+            message(&[&format!("@{}", cyclic.display())]),
+            format!(
+                "{0}:2: response file `{0}` includes itself",
+                cyclic.display()
+            )
+        );
+        assert_eq!(
+            message(&[&format!("@{}", unclosed.display())]),
+            format!("{}:2: unterminated \" quote", unclosed.display())
+        );
+        assert!(message(&["@/nonexistent/args.txt"])
+            .starts_with("@/nonexistent/args.txt: failed to read `/nonexistent/args.txt`"));
+        let unreadable = expand(&["@/nonexistent/args.txt"]).unwrap_err();
+        assert_eq!(unreadable.exit_code(), crate::EX_NOINPUT);
+        let malformed = expand(&[&format!("@{}", unclosed.display())]).unwrap_err();
+        assert_eq!(malformed.exit_code(), crate::EX_USAGE);
+
+        fs::remove_file(cyclic).unwrap();
+        fs::remove_file(unclosed).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+use crate::Arguments;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Deserializes `Arguments` from a map of argument names to values.
+///
+/// Each value is converted to a string and parsed with `FromStr` as if it were given in the
+/// command line, so the same rules apply to the fields.
+/// Strings, numbers and booleans are accepted, and the variable argument takes a sequence of
+/// them.
+/// This is used by `#[derive(Arguments)]` with `#[entrance(deserialize)]`.
+/// There is no counterpart for `Options`.
+///
+/// # Example
+/// ```
+/// use entrance::Arguments;
+///
+/// #[derive(Arguments, Debug)]
+/// #[entrance(deserialize)]
+/// struct Args {
+///     num: u32,
+///     #[entrance(variable_argument)]
+///     files: Vec<std::path::PathBuf>,
+/// }
+///
+/// let args: Args = serde_json::from_str(r#"{ "num": 3, "files": ["a.txt", "b.txt"] }"#)
+///     .unwrap();
+/// assert_eq!(args.num, 3);
+/// assert_eq!(args.files.len(), 2);
+/// ```
+pub fn deserialize_arguments<'de, A, D>(deserializer: D) -> Result<A, D::Error>
+where
+    A: Arguments,
+    D: Deserializer<'de>,
+{
+    let mut map = deserializer.deserialize_map(ArgumentMap)?;
+
+    let mut positionals = Vec::new();
+    for (i, spec) in A::spec().iter().enumerate() {
+        if i == A::var_position() {
+            take_variable_argument::<A>(&mut map, &mut positionals);
+        }
+        match map.remove(spec.name) {
+            Some(Values::Single(value)) if spec.num_values == 1 => positionals.push(value),
+            Some(Values::Multiple(values)) if values.len() == spec.num_values => {
+                positionals.extend(values)
+            }
+            Some(_) if spec.num_values == 1 => {
+                return Err(de::Error::custom(format!(
+                    "expected a single value for `{}`",
+                    spec.name
+                )));
+            }
+            Some(_) => {
+                return Err(de::Error::custom(format!(
+                    "expected {} values for `{}`",
+                    spec.num_values, spec.name
+                )));
+            }
+            None => return Err(de::Error::missing_field(spec.name)),
+        }
+    }
+    if A::var_position() >= A::spec().len() {
+        take_variable_argument::<A>(&mut map, &mut positionals);
+    }
+    if let Some(key) = map.keys().next() {
+        return Err(de::Error::custom(format!("unknown argument `{}`", key)));
+    }
+
+    A::parse(&mut positionals.into_iter()).map_err(de::Error::custom)
+}
+
+/// Moves the values of the variable argument from `map` to `positionals`.
+fn take_variable_argument<A: Arguments>(
+    map: &mut HashMap<String, Values>,
+    positionals: &mut Vec<String>,
+) {
+    if let Some(spec) = A::var_spec() {
+        match map.remove(spec.name) {
+            Some(Values::Single(value)) => positionals.push(value),
+            Some(Values::Multiple(values)) => positionals.extend(values),
+            None => {}
+        }
+    }
+}
+
+enum Values {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+struct ArgumentMap;
+
+impl<'de> Visitor<'de> for ArgumentMap {
+    type Value = HashMap<String, Values>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of argument names to values")
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut access: M) -> Result<Self::Value, M::Error> {
+        let mut map = HashMap::new();
+        while let Some(key) = access.next_key::<String>()? {
+            let values = access.next_value::<Values>()?;
+            if map.insert(key.clone(), values).is_some() {
+                return Err(de::Error::custom(format!("duplicate argument `{}`", key)));
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl<'de> Deserialize<'de> for Values {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValuesVisitor)
+    }
+}
+
+struct ValuesVisitor;
+
+impl<'de> Visitor<'de> for ValuesVisitor {
+    type Value = Values;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string, a number, a boolean or a sequence of them")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Values, E> {
+        Ok(Values::Single(v.to_string()))
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Values, E> {
+        Ok(Values::Single(v.to_string()))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Values, E> {
+        Ok(Values::Single(v.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Values, E> {
+        Ok(Values::Single(v.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Values, E> {
+        Ok(Values::Single(v.to_string()))
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut access: S) -> Result<Values, S::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = access.next_element::<Values>()? {
+            match value {
+                Values::Single(value) => values.push(value),
+                Values::Multiple(_) => {
+                    return Err(de::Error::custom("nested sequences are not supported"));
+                }
+            }
+        }
+        Ok(Values::Multiple(values))
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{Arg, HelpDisplay, ValueKind};
+use std::fmt::{self, Write};
+
+/// The version of the schema emitted by `JsonSpec`.
+///
+/// It is incremented when a field is removed or its meaning changes.
+/// Adding a field does not change the version.
+pub const SPEC_SCHEMA_VERSION: u32 = 1;
+
+/// Helper struct for exporting the spec of a command as JSON with `format!` and `{}`.
+///
+/// The output is an object with the following fields:
+///
+/// - `schema_version`: `SPEC_SCHEMA_VERSION`
+/// - `name`, `version`: strings
+/// - `about`, `authors`: strings or `null`
+/// - `options`: an array of objects with `long`, `short` (a string or `null`), `description`,
+///   `value` (`"flag"`, `"required"` or `"optional"`), `allow_hyphen_values`, `value_name` (a string or
+///   `null`), `hidden`, `deprecated` (a string or `null`), and `aliases`, `visible_aliases`,
+///   `short_aliases`, `visible_short_aliases`, `deprecated_aliases` and
+///   `deprecated_short_aliases` (arrays of strings), `delimiter` (a string or
+///   `null`), `min_occurrences`, `max_occurrences` (a number or `null`), `separator` and
+///   `implicit_value` (strings or `null`), including the built-in options.
+///   Hidden options are included so that tools like completion generators can skip them
+/// - `arguments`: an array of objects with `name`, `description`, `value_name` and `hint`
+///   (strings or `null`), `num_values`, `value_names` (an array of strings), `hidden` and
+///   `deprecated` (a string or `null`), including the hidden arguments
+/// - `variable_argument`: an object like an element of `arguments` with `position`
+///   (the number of `arguments` before it), `min_values` and `max_values` (a number or `null`),
+///   or `null`
+/// - `subcommands`: an array of objects like the root one without `schema_version`,
+///   which is always empty for now
+#[derive(Debug)]
+pub struct JsonSpec<'a> {
+    help: HelpDisplay<'a>,
+}
+
+impl<'a> JsonSpec<'a> {
+    /// `help` should include the hidden options.
+    pub(crate) fn new(help: HelpDisplay<'a>) -> Self {
+        Self { help }
+    }
+}
+
+impl<'a> fmt::Display for JsonSpec<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let help = &self.help;
+        let info = help.info;
+        writeln!(f, "{{")?;
+        writeln!(f, "  \"schema_version\": {},", SPEC_SCHEMA_VERSION)?;
+        writeln!(f, "  \"name\": {},", JsonStr(&info.name))?;
+        writeln!(f, "  \"version\": {},", JsonStr(&info.version))?;
+        writeln!(f, "  \"about\": {},", JsonOption(info.about.as_deref()))?;
+        writeln!(f, "  \"authors\": {},", JsonOption(info.authors.as_deref()))?;
+
+        write!(f, "  \"options\": ")?;
+        write_array(f, &help.options, |f, opt| {
+            let value = match opt.value {
+                ValueKind::Flag => "flag",
+                ValueKind::Required => "required",
+                ValueKind::Optional => "optional",
+            };
+            let short = opt.short.map(|c| c.to_string());
+            writeln!(f, "{{")?;
+            writeln!(f, "      \"long\": {},", JsonStr(opt.long))?;
+            writeln!(f, "      \"short\": {},", JsonOption(short.as_deref()))?;
+            writeln!(f, "      \"description\": {},", JsonStr(opt.description))?;
+            writeln!(f, "      \"value\": {},", JsonStr(value))?;
+            writeln!(
+                f,
+                "      \"allow_hyphen_values\": {},",
+                opt.allow_hyphen_values
+            )?;
+            writeln!(f, "      \"value_name\": {},", JsonOption(opt.value_name))?;
+            writeln!(f, "      \"hidden\": {},", opt.hidden)?;
+            writeln!(f, "      \"deprecated\": {},", JsonOption(opt.deprecated))?;
+            let short_aliases: Vec<_> = opt.short_aliases.iter().map(char::to_string).collect();
+            let visible_short_aliases: Vec<_> = opt
+                .visible_short_aliases
+                .iter()
+                .map(char::to_string)
+                .collect();
+            writeln!(f, "      \"aliases\": {},", JsonList(opt.aliases))?;
+            writeln!(
+                f,
+                "      \"visible_aliases\": {},",
+                JsonList(opt.visible_aliases)
+            )?;
+            writeln!(f, "      \"short_aliases\": {},", JsonList(&short_aliases))?;
+            writeln!(
+                f,
+                "      \"visible_short_aliases\": {},",
+                JsonList(&visible_short_aliases)
+            )?;
+            let deprecated_short_aliases: Vec<_> = opt
+                .deprecated_short_aliases
+                .iter()
+                .map(char::to_string)
+                .collect();
+            writeln!(
+                f,
+                "      \"deprecated_aliases\": {},",
+                JsonList(opt.deprecated_aliases)
+            )?;
+            writeln!(
+                f,
+                "      \"deprecated_short_aliases\": {},",
+                JsonList(&deprecated_short_aliases)
+            )?;
+            let delimiter = opt.delimiter.map(|c| c.to_string());
+            let max_occurrences = opt.max_occurrences.map(|max| max.to_string());
+            let separator = opt.separator.map(|c| c.to_string());
+            writeln!(
+                f,
+                "      \"delimiter\": {},",
+                JsonOption(delimiter.as_deref())
+            )?;
+            writeln!(f, "      \"min_occurrences\": {},", opt.min_occurrences)?;
+            writeln!(
+                f,
+                "      \"max_occurrences\": {},",
+                max_occurrences.as_deref().unwrap_or("null")
+            )?;
+            writeln!(
+                f,
+                "      \"separator\": {},",
+                JsonOption(separator.as_deref())
+            )?;
+            writeln!(
+                f,
+                "      \"implicit_value\": {}",
+                JsonOption(opt.implicit_value)
+            )?;
+            write!(f, "    }}")
+        })?;
+        writeln!(f, ",")?;
+
+        write!(f, "  \"arguments\": ")?;
+        write_array(f, &help.arguments, |f, arg| {
+            write_argument(f, arg, "    ")?;
+            writeln!(f)?;
+            write!(f, "    }}")
+        })?;
+        writeln!(f, ",")?;
+
+        write!(f, "  \"variable_argument\": ")?;
+        match &help.variable_argument {
+            Some(arg) => {
+                write_argument(f, arg, "  ")?;
+                let max_values = arg.max_values.map(|max| max.to_string());
+                writeln!(f, ",")?;
+                writeln!(f, "    \"position\": {},", help.variable_position)?;
+                writeln!(f, "    \"min_values\": {},", arg.min_values)?;
+                writeln!(
+                    f,
+                    "    \"max_values\": {}",
+                    max_values.as_deref().unwrap_or("null")
+                )?;
+                write!(f, "  }}")?;
+            }
+            None => write!(f, "null")?,
+        }
+        writeln!(f, ",")?;
+
+        writeln!(f, "  \"subcommands\": []")?;
+        writeln!(f, "}}")
+    }
+}
+
+fn write_array<T, F>(f: &mut fmt::Formatter, items: &[T], mut write_item: F) -> fmt::Result
+where
+    F: FnMut(&mut fmt::Formatter, &T) -> fmt::Result,
+{
+    if items.is_empty() {
+        return write!(f, "[]");
+    }
+    writeln!(f, "[")?;
+    for (i, item) in items.iter().enumerate() {
+        write!(f, "    ")?;
+        write_item(f, item)?;
+        if i + 1 < items.len() {
+            write!(f, ",")?;
+        }
+        writeln!(f)?;
+    }
+    write!(f, "  ]")
+}
+
+/// Writes an object of the argument without the closing brace and the last newline.
+fn write_argument(f: &mut fmt::Formatter, arg: &Arg, indent: &str) -> fmt::Result {
+    writeln!(f, "{{")?;
+    writeln!(f, "{}  \"name\": {},", indent, JsonStr(arg.name))?;
+    writeln!(
+        f,
+        "{}  \"description\": {},",
+        indent,
+        JsonStr(arg.description)
+    )?;
+    writeln!(
+        f,
+        "{}  \"value_name\": {},",
+        indent,
+        JsonOption(arg.value_name)
+    )?;
+    writeln!(f, "{}  \"hint\": {},", indent, JsonOption(arg.hint))?;
+    writeln!(f, "{}  \"num_values\": {},", indent, arg.num_values)?;
+    writeln!(
+        f,
+        "{}  \"value_names\": {},",
+        indent,
+        JsonList(arg.value_names)
+    )?;
+    writeln!(f, "{}  \"hidden\": {},", indent, arg.hidden)?;
+    write!(
+        f,
+        "{}  \"deprecated\": {}",
+        indent,
+        JsonOption(arg.deprecated)
+    )
+}
+
+/// A string quoted and escaped as a JSON string.
+struct JsonStr<'a>(&'a str);
+
+impl<'a> fmt::Display for JsonStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+/// A JSON string, or `null` for `None`.
+struct JsonOption<'a>(Option<&'a str>);
+
+impl<'a> fmt::Display for JsonOption<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(s) => write!(f, "{}", JsonStr(s)),
+            None => f.write_str("null"),
+        }
+    }
+}
+
+/// A JSON array of strings on a single line.
+struct JsonList<'a, S>(&'a [S]);
+
+impl<'a, S: AsRef<str>> fmt::Display for JsonList<'a, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_char('[')?;
+        for (i, s) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", JsonStr(s.as_ref()))?;
+        }
+        f.write_char(']')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape() {
+        assert_eq!(
+            JsonStr("a \"b\"\\\n\u{1}").to_string(),
+            r#""a \"b\"\\\n\u0001""#
+        );
+    }
+
+    #[test]
+    fn list() {
+        assert_eq!(JsonList::<&str>(&[]).to_string(), "[]");
+        assert_eq!(JsonList(&["a", "\"b\""]).to_string(), r#"["a", "\"b\""]"#);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::ValueName;
+use std::convert::Infallible;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The value name of `Input` and `Output` shown in help messages.
+pub const STDIO_VALUE_NAME: &str = "FILE|-";
+
+/// An argument for reading from a file, or stdin if `-` is given.
+///
+/// The file is opened at the first read.
+///
+/// # Example
+/// ```
+/// use entrance::Input;
+///
+/// let input: Input = "-".parse().unwrap();
+/// assert!(input.is_stdin());
+///
+/// let input: Input = "path/to/file".parse().unwrap();
+/// assert_eq!(input.path(), Some(std::path::Path::new("path/to/file")));
+/// ```
+#[derive(Debug)]
+pub struct Input {
+    path: Option<PathBuf>,
+    file: Option<File>,
+}
+
+impl Input {
+    pub fn is_stdin(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// Returns the path to the file, or `None` for stdin.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+impl FromStr for Input {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            path: parse_path(s),
+            file: None,
+        })
+    }
+}
+
+impl ValueName for Input {
+    const VALUE_NAME: &'static str = STDIO_VALUE_NAME;
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &self.path {
+            None => io::stdin().read(buf),
+            Some(path) => {
+                if self.file.is_none() {
+                    self.file = Some(File::open(path)?);
+                }
+                self.file.as_mut().unwrap().read(buf)
+            }
+        }
+    }
+}
+
+/// An argument for writing to a file, or stdout if `-` is given.
+///
+/// The file is created at the first write.
+#[derive(Debug)]
+pub struct Output {
+    path: Option<PathBuf>,
+    file: Option<File>,
+}
+
+impl Output {
+    pub fn is_stdout(&self) -> bool {
+        self.path.is_none()
+    }
+
+    /// Returns the path to the file, or `None` for stdout.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+impl FromStr for Output {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            path: parse_path(s),
+            file: None,
+        })
+    }
+}
+
+impl ValueName for Output {
+    const VALUE_NAME: &'static str = STDIO_VALUE_NAME;
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &self.path {
+            None => io::stdout().write(buf),
+            Some(path) => {
+                if self.file.is_none() {
+                    self.file = Some(File::create(path)?);
+                }
+                self.file.as_mut().unwrap().write(buf)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None if self.path.is_none() => io::stdout().flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Serializes the path, or `-` for stdin.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Input {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_path(self.path(), serializer)
+    }
+}
+
+/// Serializes the path, or `-` for stdout.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Output {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_path(self.path(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_path<S: serde::Serializer>(
+    path: Option<&Path>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match path {
+        Some(path) => serde::Serialize::serialize(path, serializer),
+        None => serializer.serialize_str("-"),
+    }
+}
+
+fn parse_path(s: &str) -> Option<PathBuf> {
+    if s == "-" {
+        None
+    } else {
+        Some(PathBuf::from(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read() -> io::Result<()> {
+        let path = std::env::temp_dir().join(format!("entrance-stdio-{}", std::process::id()));
+        let path_str = path.to_str().unwrap();
+
+        let mut output: Output = path_str.parse().unwrap();
+        assert!(!output.is_stdout());
+        assert!(!path.exists());
+        output.write_all(b"hello")?;
+        output.flush()?;
+
+        let mut input: Input = path_str.parse().unwrap();
+        let mut content = String::new();
+        input.read_to_string(&mut content)?;
+        assert_eq!(content, "hello");
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
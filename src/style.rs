@@ -0,0 +1,133 @@
+use std::ffi::OsString;
+use std::fmt;
+use std::io::IsTerminal;
+
+/// Whether to color the help message and errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Colors only if the stream is a terminal.
+    ///
+    /// `NO_COLOR` with a non-empty value disables colors, and otherwise `CLICOLOR_FORCE` with
+    /// a value other than `0` enables them even if the stream is not a terminal.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Returns `true` if the output to stdout should be colored.
+    pub fn for_stdout(self) -> bool {
+        self.resolve(std::io::stdout().is_terminal())
+    }
+
+    /// Returns `true` if the output to stderr should be colored.
+    pub fn for_stderr(self) -> bool {
+        self.resolve(std::io::stderr().is_terminal())
+    }
+
+    fn resolve(self, is_terminal: bool) -> bool {
+        resolve(
+            self,
+            std::env::var_os("NO_COLOR"),
+            std::env::var_os("CLICOLOR_FORCE"),
+            is_terminal,
+        )
+    }
+}
+
+fn resolve(
+    choice: ColorChoice,
+    no_color: Option<OsString>,
+    clicolor_force: Option<OsString>,
+    is_terminal: bool,
+) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            if no_color.is_some_and(|value| !value.is_empty()) {
+                false
+            } else if clicolor_force.is_some_and(|value| value != "0") {
+                true
+            } else {
+                is_terminal
+            }
+        }
+    }
+}
+
+/// ANSI escape sequences used for coloring.
+///
+/// # Example
+/// ```
+/// use entrance::{Command, Styles};
+///
+/// let command = Command::<(), ()>::new("sample", "1.0.0").styles(Styles {
+///     heading: "\x1b[1;33m",
+///     ..Styles::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Styles {
+    /// Headings in the help message, e.g. `USAGE:`
+    pub heading: &'static str,
+    /// Names of options and arguments in the help message
+    pub literal: &'static str,
+    /// The `Error:` label
+    pub error: &'static str,
+}
+
+impl Default for Styles {
+    fn default() -> Self {
+        Self {
+            heading: "\x1b[1;4m",
+            literal: "\x1b[1m",
+            error: "\x1b[1;31m",
+        }
+    }
+}
+
+/// A content wrapped with an escape sequence and a reset if the style is given.
+pub(crate) struct Painted<T> {
+    style: Option<&'static str>,
+    content: T,
+}
+
+pub(crate) fn paint<T: fmt::Display>(style: Option<&'static str>, content: T) -> Painted<T> {
+    Painted { style, content }
+}
+
+impl<T: fmt::Display> fmt::Display for Painted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.style {
+            Some(style) => write!(f, "{}{}\x1b[m", style, self.content),
+            None => write!(f, "{}", self.content),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn choice() {
+        let set = |value: &str| Some(OsString::from(value));
+        assert!(resolve(ColorChoice::Auto, None, None, true));
+        assert!(!resolve(ColorChoice::Auto, None, None, false));
+        assert!(!resolve(ColorChoice::Auto, set("1"), None, true));
+        assert!(resolve(ColorChoice::Auto, set(""), None, true));
+        assert!(resolve(ColorChoice::Auto, None, set("1"), false));
+        assert!(!resolve(ColorChoice::Auto, None, set("0"), false));
+        assert!(!resolve(ColorChoice::Auto, set("1"), set("1"), true));
+        assert!(resolve(ColorChoice::Always, set("1"), None, false));
+        assert!(!resolve(ColorChoice::Never, None, set("1"), true));
+    }
+
+    #[test]
+    fn painted() {
+        assert_eq!(paint(Some("\x1b[1m"), "a").to_string(), "\x1b[1ma\x1b[m");
+        assert_eq!(paint(None, "a").to_string(), "a");
+    }
+}
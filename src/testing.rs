@@ -0,0 +1,166 @@
+//! Utilities for testing command line interfaces built on `entrance`.
+//!
+//! Unlike `parse`, the helpers here never exit the process, and capture the help message,
+//! the version and the output of informative options printed with `entrance::print_output`.
+//! Warnings are still passed to the warning sink of the command.
+//!
+//! # Example
+//! ```
+//! use entrance::testing::TryParseFrom;
+//! use entrance::{Command, Options};
+//!
+//! #[derive(Options, Debug, PartialEq)]
+//! enum Opts {
+//!     #[entrance(short = 'v')]
+//!     Verbose,
+//! }
+//!
+//! let command = Command::<Opts, ()>::new("prog", "1.0.0");
+//!
+//! let (opts, ()) = command.try_parse_from(&["prog", "-v"]).unwrap().unwrap();
+//! assert_eq!(opts, [Opts::Verbose]);
+//!
+//! let version = command.try_parse_from(&["prog", "--version"]).unwrap();
+//! assert_eq!(version.output(), Some("prog 1.0.0"));
+//!
+//! entrance::assert_parse_error!(command, ["prog", "--invalid"], entrance::Error::InvalidOption);
+//! ```
+
+use crate::{Arguments, Command, CommandBuilder, Matches, Options, Result};
+use std::cell::RefCell;
+use std::convert::Infallible;
+use std::fmt::{self, Write};
+
+thread_local! {
+    static CAPTURED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// The result of parsing arguments successfully.
+#[derive(Debug)]
+pub enum Outcome<T, O> {
+    /// The parsed values
+    Parsed(T),
+    /// The built-in help option is given, with the help message.
+    Help(String),
+    /// The built-in version option is given, with the version message.
+    Version(String),
+    /// An informative option is given, with the output of its callback.
+    ///
+    /// Only the output printed with `entrance::print_output`, like the one of `entrance::help`
+    /// and `entrance::json_spec`, is captured.
+    /// Other side effects of the callback, e.g. `println!`, are not.
+    Informative(O, String),
+}
+
+impl<T, O> Outcome<T, O> {
+    /// Returns the parsed values.
+    ///
+    /// # Panics
+    /// Panics if the outcome is not `Parsed`.
+    pub fn unwrap(self) -> T {
+        match self {
+            Outcome::Parsed(parsed) => parsed,
+            Outcome::Help(_) => panic!("called `Outcome::unwrap()` on a `Help` value"),
+            Outcome::Version(_) => panic!("called `Outcome::unwrap()` on a `Version` value"),
+            Outcome::Informative(..) => {
+                panic!("called `Outcome::unwrap()` on an `Informative` value")
+            }
+        }
+    }
+
+    /// Returns the message which would be printed for the built-in and informative options.
+    pub fn output(&self) -> Option<&str> {
+        match self {
+            Outcome::Help(message) | Outcome::Version(message) => Some(message),
+            Outcome::Informative(_, output) => Some(output),
+            Outcome::Parsed(_) => None,
+        }
+    }
+}
+
+/// A trait for parsing a slice of `&str` including the program name without side effects.
+pub trait TryParseFrom {
+    type Output;
+    /// The type of informative options
+    type Informative;
+
+    fn try_parse_from(&self, args: &[&str]) -> Result<Outcome<Self::Output, Self::Informative>>;
+}
+
+impl<Opts: Options, Args: Arguments> TryParseFrom for Command<Opts, Args> {
+    type Output = (Vec<Opts>, Args);
+    type Informative = Opts;
+
+    fn try_parse_from(&self, args: &[&str]) -> Result<Outcome<Self::Output, Opts>> {
+        Ok(match self.try_parse(to_strings(args), false)? {
+            Outcome::Parsed((opts, args, _)) => Outcome::Parsed((opts, args)),
+            Outcome::Help(message) => Outcome::Help(message),
+            Outcome::Version(message) => Outcome::Version(message),
+            Outcome::Informative(opt, _) => {
+                let output = capture(|| opt.trigger_informative(self));
+                Outcome::Informative(opt, output)
+            }
+        })
+    }
+}
+
+impl<'a> TryParseFrom for CommandBuilder<'a> {
+    type Output = Matches;
+    type Informative = Infallible;
+
+    fn try_parse_from(&self, args: &[&str]) -> Result<Outcome<Matches, Infallible>> {
+        self.try_parse(to_strings(args), false)
+    }
+}
+
+/// Runs `f`, returning what it printed with `print_output` instead of printing it.
+fn capture(f: impl FnOnce()) -> String {
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(String::new()));
+    f();
+    CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default())
+}
+
+/// Returns `true` while `try_parse_from` is capturing the output of an informative option.
+pub(crate) fn capturing() -> bool {
+    CAPTURED.with(|captured| captured.borrow().is_some())
+}
+
+pub(crate) fn print_output(output: &dyn fmt::Display) {
+    CAPTURED.with(|captured| match &mut *captured.borrow_mut() {
+        Some(buffer) => write!(buffer, "{}", output).unwrap(),
+        None => print!("{}", output),
+    })
+}
+
+fn to_strings<'a>(args: &'a [&str]) -> impl Iterator<Item = String> + 'a {
+    args.iter().map(|arg| arg.to_string())
+}
+
+/// Asserts that parsing the arguments fails with an error matching the pattern.
+///
+/// The pattern may have an `if` guard like `matches!`.
+///
+/// # Example
+/// ```
+/// use entrance::{Arg, CommandBuilder, Error};
+///
+/// let command = CommandBuilder::new("prog", "1.0.0").argument(Arg::new("num", ""));
+/// entrance::assert_parse_error!(command, ["prog"], Error::InvalidNumberOfArguments);
+/// ```
+#[macro_export]
+macro_rules! assert_parse_error {
+    ($command:expr, $args:expr, $pattern:pat $(if $guard:expr)? $(,)?) => {
+        match $crate::testing::TryParseFrom::try_parse_from(&$command, &$args) {
+            Err($pattern) $(if $guard)? => {}
+            Err(err) => panic!(
+                "expected an error matching `{}`, but got `{:?}`",
+                stringify!($pattern),
+                err
+            ),
+            Ok(_) => panic!(
+                "expected an error matching `{}`, but parsing succeeded",
+                stringify!($pattern)
+            ),
+        }
+    };
+}
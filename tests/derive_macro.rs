@@ -1,4 +1,7 @@
-use entrance::{Arguments, Error, OptionItem, Options};
+// Some tests predate `matches!` and are kept as they were written.
+#![allow(clippy::match_like_matches_macro)]
+
+use entrance::{ArgCursor, Arguments, Error, OptionItem, Options};
 use std::path::PathBuf;
 
 #[test]
@@ -29,16 +32,28 @@ fn options() -> Result<(), entrance::Error> {
         Help,
     }
 
-    let option = Opts::parse(OptionItem::Long("verbose".to_string()))?;
+    let option = Opts::parse(
+        OptionItem::Long("verbose".to_string()),
+        &mut ArgCursor::default(),
+    )?;
     assert_eq!(option, Opts::Verbose);
 
-    let option = Opts::parse(OptionItem::Long("version".to_string()))?;
+    let option = Opts::parse(
+        OptionItem::Long("version".to_string()),
+        &mut ArgCursor::default(),
+    )?;
     assert_eq!(option, Opts::Version);
 
-    let option = Opts::parse(OptionItem::Long("help".to_string()))?;
+    let option = Opts::parse(
+        OptionItem::Long("help".to_string()),
+        &mut ArgCursor::default(),
+    )?;
     assert_eq!(option, Opts::Help);
 
-    let option = Opts::parse(OptionItem::Long("invalid".to_string()));
+    let option = Opts::parse(
+        OptionItem::Long("invalid".to_string()),
+        &mut ArgCursor::default(),
+    );
     assert!(option.is_err());
     let is_invalid_option = match option.unwrap_err() {
         Error::InvalidOption => true,
@@ -48,3 +63,1013 @@ fn options() -> Result<(), entrance::Error> {
 
     Ok(())
 }
+
+/// A struct with a visibility, doc comments and generic parameters.
+#[derive(Arguments)]
+pub struct GenericArgs<P: std::str::FromStr>
+where
+    P::Err: std::error::Error + 'static,
+{
+    #[entrance(description = "Any value parsed with FromStr")]
+    value: P,
+
+    /// Remaining values
+    #[entrance(variable_argument)]
+    rest: Vec<P>,
+}
+
+#[test]
+fn struct_with_generics() -> entrance::Result<()> {
+    let args = ["1", "2", "3"];
+    let result = GenericArgs::<u8>::parse(&mut args.iter().map(|s| s.to_string()))?;
+
+    assert_eq!(result.value, 1);
+    assert_eq!(result.rest, vec![2, 3]);
+    assert_eq!(GenericArgs::<u8>::spec()[0].name, "value");
+
+    Ok(())
+}
+
+#[test]
+fn options_with_visibility_and_attributes() -> entrance::Result<()> {
+    /// Options documented on the type
+    #[derive(Options, Debug, PartialEq)]
+    #[allow(dead_code)]
+    pub(crate) enum Opts {
+        /// Documented variant
+        #[entrance(short = 'v')]
+        Verbose,
+    }
+
+    let option = Opts::parse(OptionItem::Short('v'), &mut ArgCursor::default())?;
+    assert_eq!(option, Opts::Verbose);
+
+    Ok(())
+}
+
+#[test]
+fn renamed_options() -> entrance::Result<()> {
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(long = "dry-run", short = 'n')]
+        DryRun,
+    }
+
+    let option = Opts::parse(
+        OptionItem::Long("dry-run".to_string()),
+        &mut ArgCursor::default(),
+    )?;
+    assert_eq!(option, Opts::DryRun);
+    assert!(Opts::parse(
+        OptionItem::Long("dryrun".to_string()),
+        &mut ArgCursor::default()
+    )
+    .is_err());
+    assert_eq!(Opts::spec()[0].long, "dry-run");
+
+    Ok(())
+}
+
+#[test]
+fn options_with_values() -> entrance::Result<()> {
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(short = 'v')]
+        Verbose,
+        #[entrance(short = 'o')]
+        Output(PathBuf),
+        Level(u8),
+    }
+
+    fn parse(cursor: &mut ArgCursor) -> entrance::Result<Opts> {
+        let option = cursor.next_option()?.unwrap();
+        Opts::parse(option, cursor)
+    }
+
+    let args = ["-vofile", "--level=3", "--output", "path", "--level"];
+    let mut cursor = ArgCursor::new(args.iter().map(|s| s.to_string()));
+
+    assert_eq!(parse(&mut cursor)?, Opts::Verbose);
+    assert_eq!(parse(&mut cursor)?, Opts::Output(PathBuf::from("file")));
+    assert_eq!(parse(&mut cursor)?, Opts::Level(3));
+    assert_eq!(parse(&mut cursor)?, Opts::Output(PathBuf::from("path")));
+    assert!(matches!(
+        parse(&mut cursor),
+        Err(Error::MissingValue(option)) if option == "--level"
+    ));
+
+    assert_eq!(Opts::spec()[0].value, entrance::ValueKind::Flag);
+    assert_eq!(Opts::spec()[1].value, entrance::ValueKind::Required);
+
+    Ok(())
+}
+
+#[test]
+fn negative_numbers() -> entrance::Result<()> {
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        Offset(f64),
+        #[entrance(allow_hyphen_values)]
+        Pattern(String),
+    }
+
+    #[derive(Arguments)]
+    struct Args {
+        x: f64,
+        y: f64,
+    }
+
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0");
+    let args = ["sample", "--offset", "-1", "--pattern", "-foo", "-5", "3.2"];
+    let (opts, args) = command.parse(args.iter().map(|s| s.to_string()))?;
+
+    assert_eq!(
+        opts,
+        vec![Opts::Offset(-1.0), Opts::Pattern("-foo".to_string())]
+    );
+    assert_eq!(args.x, -5.0);
+    assert_eq!(args.y, 3.2);
+
+    let args = ["sample", "--offset", "-x"];
+    let result = command.parse(args.iter().map(|s| s.to_string()));
+    assert!(matches!(result, Err(Error::MissingValue(_))));
+
+    Ok(())
+}
+
+#[test]
+fn stdio_arguments() -> entrance::Result<()> {
+    use entrance::{Input, Output};
+
+    #[derive(Options)]
+    #[allow(dead_code)]
+    enum Opts {
+        Log(Option<Output>),
+    }
+
+    #[derive(Arguments)]
+    struct Args {
+        input: Input,
+        #[entrance(variable_argument)]
+        outputs: Vec<Output>,
+    }
+
+    let args = ["-", "-", "path/to/file"];
+    let args = Args::parse(&mut args.iter().map(|s| s.to_string()))?;
+    assert!(args.input.is_stdin());
+    assert!(args.outputs[0].is_stdout());
+    assert_eq!(
+        args.outputs[1].path(),
+        Some(std::path::Path::new("path/to/file"))
+    );
+
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0")
+        .help_option(None)
+        .version_option(None);
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    sample [OPTIONS] <FILE|-> [FILE|-]...
+
+OPTIONS:
+    --log[=<FILE|->]    
+
+ARGS:
+    input      
+    outputs    
+"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn response_files() -> entrance::Result<()> {
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(short = 'v')]
+        Verbose,
+        Level(u32),
+    }
+
+    #[derive(Arguments)]
+    struct Args {
+        #[entrance(variable_argument)]
+        files: Vec<String>,
+    }
+
+    let path = std::env::temp_dir().join(format!("entrance-args-{}", std::process::id()));
+    std::fs::write(&path, "--level 3\n'a file.txt'\n").unwrap();
+    let response = format!("@{}", path.display());
+    let args = ["sample", "-v", &response, "b.txt"];
+
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0").response_files(true);
+    let (opts, args) = command.parse(args.iter().map(|s| s.to_string()))?;
+    assert_eq!(opts, [Opts::Verbose, Opts::Level(3)]);
+    assert_eq!(args.files, ["a file.txt", "b.txt"]);
+
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0");
+    let (_, args) = command.parse(["sample", &response].iter().map(|s| s.to_string()))?;
+    assert_eq!(args.files, [response]);
+
+    std::fs::remove_file(path).unwrap();
+    Ok(())
+}
+
+#[test]
+fn config_sources() -> entrance::Result<()> {
+    use entrance::{EnvSource, KeyValueFile, ValueSource};
+
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(short = 'v')]
+        Verbose,
+        DryRun,
+        Level(i32),
+        Exclude(String),
+    }
+
+    #[derive(Arguments)]
+    struct Args {
+        input: String,
+        output: String,
+        #[entrance(variable_argument)]
+        rest: Vec<String>,
+    }
+
+    std::env::set_var("ENTRANCE_CONFIG_LEVEL", "-2");
+    std::env::set_var("ENTRANCE_CONFIG_OUTPUT", "env.txt");
+    let config: KeyValueFile = "\
+verbose = true
+dryrun = false
+level = 5
+exclude = a
+exclude = b
+output = config.txt
+rest = x
+"
+    .parse()?;
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0")
+        .config_source(EnvSource::new("ENTRANCE_CONFIG"))
+        .config_source(config);
+
+    let args = ["sample", "--verbose", "in.txt"];
+    let (opts, args, sources) = command.parse_with_sources(args.iter().map(|s| s.to_string()))?;
+    assert_eq!(
+        opts,
+        [
+            Opts::Verbose,
+            Opts::Level(-2),
+            Opts::Exclude("a".to_string()),
+            Opts::Exclude("b".to_string()),
+        ]
+    );
+    assert_eq!(
+        (args.input.as_str(), args.output.as_str()),
+        ("in.txt", "env.txt")
+    );
+    assert_eq!(args.rest, ["x"]);
+
+    assert_eq!(sources.option("verbose"), ValueSource::CommandLine);
+    assert_eq!(sources.option("dryrun"), ValueSource::Default);
+    assert_eq!(sources.option("level"), ValueSource::Env);
+    assert_eq!(sources.option("exclude"), ValueSource::Config);
+    assert_eq!(sources.argument("input"), ValueSource::CommandLine);
+    assert_eq!(sources.argument("output"), ValueSource::Env);
+    assert_eq!(sources.argument("rest"), ValueSource::Config);
+
+    let invalid: KeyValueFile = "dryrun = maybe".parse()?;
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0").config_source(invalid);
+    let err = command
+        .parse(["sample", "a", "b"].iter().map(|s| s.to_string()))
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "`dryrun`: expected a boolean for a flag, found `maybe`"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn config_sources_variable_argument_position() -> entrance::Result<()> {
+    use entrance::{KeyValueFile, ValueSource};
+
+    #[derive(Arguments)]
+    struct Args {
+        first: String,
+        #[entrance(variable_argument)]
+        middle: Vec<String>,
+        last: String,
+    }
+
+    let config: KeyValueFile = "middle = x\nmiddle = y\nlast = z\n".parse()?;
+    let command = entrance::Command::<(), Args>::new("sample", "1.0.0").config_source(config);
+
+    let (_, args, sources) =
+        command.parse_with_sources(["sample", "a"].iter().map(|s| s.to_string()))?;
+    assert_eq!(
+        (args.first.as_str(), args.middle, args.last.as_str()),
+        ("a", vec!["x".to_string(), "y".to_string()], "z")
+    );
+    assert_eq!(sources.argument("first"), ValueSource::CommandLine);
+    assert_eq!(sources.argument("middle"), ValueSource::Config);
+    assert_eq!(sources.argument("last"), ValueSource::Config);
+
+    let args = ["sample", "a", "b", "c"].iter().map(|s| s.to_string());
+    let (_, args, sources) = command.parse_with_sources(args)?;
+    assert_eq!(
+        (args.middle, args.last.as_str()),
+        (vec!["b".to_string()], "c")
+    );
+    assert_eq!(sources.argument("middle"), ValueSource::CommandLine);
+    assert_eq!(sources.argument("last"), ValueSource::CommandLine);
+
+    Ok(())
+}
+
+#[test]
+fn config_sources_shared_key() -> entrance::Result<()> {
+    #[derive(Options)]
+    enum Opts {
+        Output,
+    }
+
+    #[derive(Arguments)]
+    struct Args {
+        output: String,
+    }
+
+    let command_line = || ["sample", "out.txt"].iter().map(|s| s.to_string());
+    let (_, args) = entrance::Command::<Opts, Args>::new("sample", "1.0.0").parse(command_line())?;
+    assert_eq!(args.output, "out.txt");
+
+    let config: entrance::KeyValueFile = "output = true".parse()?;
+    let err = entrance::Command::<Opts, Args>::new("sample", "1.0.0")
+        .config_source(config)
+        .parse(command_line())
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "`output`: the key is shared by an option and an argument"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde() {
+    #[derive(Arguments, Debug)]
+    #[entrance(deserialize)]
+    struct Args<P: std::str::FromStr>
+    where
+        P::Err: std::error::Error + 'static,
+    {
+        num: i32,
+        flag: bool,
+        #[entrance(variable_argument)]
+        paths: Vec<P>,
+    }
+
+    let args: Args<std::path::PathBuf> =
+        serde_json::from_str(r#"{ "num": -3, "flag": true, "paths": ["a", "b"] }"#).unwrap();
+    assert_eq!(args.num, -3);
+    assert!(args.flag);
+    assert_eq!(args.paths.len(), 2);
+
+    let args: Args<u32> = serde_json::from_str(r#"{ "num": "4", "flag": false }"#).unwrap();
+    assert_eq!(args.num, 4);
+    assert!(args.paths.is_empty());
+
+    let error = |json| {
+        serde_json::from_str::<Args<u32>>(json)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(error(r#"{ "num": 1 }"#), "missing field `flag`");
+    assert_eq!(
+        error(r#"{ "num": 1, "flag": true, "other": 2 }"#),
+        "unknown argument `other`"
+    );
+    assert_eq!(error(r#"{ "num": "x", "flag": true }"#), "Failed to parse");
+
+    let spec = serde_json::to_string(&<Args<u32> as entrance::Arguments>::spec()[0]).unwrap();
+    assert_eq!(
+        spec,
+        r#"{"name":"num","description":"","value_name":null,"hint":"INTEGER","num_values":1,"value_names":[],"min_values":0,"max_values":null,"hidden":false,"deprecated":null}"#
+    );
+
+    #[derive(Arguments, entrance::serde::Serialize)]
+    #[serde(crate = "entrance::serde")]
+    struct Files {
+        input: entrance::Input,
+        #[entrance(variable_argument)]
+        outputs: Vec<entrance::Output>,
+    }
+
+    let files = Files::parse(&mut ["-", "out.txt", "-"].iter().map(|s| s.to_string())).unwrap();
+    assert_eq!(
+        serde_json::to_string(&files).unwrap(),
+        r#"{"input":"-","outputs":["out.txt","-"]}"#
+    );
+}
+
+#[test]
+fn json_spec() {
+    #[derive(Options)]
+    #[allow(dead_code)]
+    enum Opts {
+        #[entrance(description = "Write to a \"file\"", short = 'o')]
+        Output(PathBuf),
+    }
+
+    #[derive(Arguments)]
+    #[allow(dead_code)]
+    struct Args {
+        input: entrance::Input,
+    }
+
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0");
+    let spec: serde_json::Value = serde_json::from_str(&command.json_spec().to_string()).unwrap();
+    assert_eq!(spec["schema_version"], entrance::SPEC_SCHEMA_VERSION);
+    assert_eq!(spec["options"][0]["description"], "Write to a \"file\"");
+    assert_eq!(spec["options"][0]["value"], "required");
+    assert_eq!(spec["options"][2]["long"], "version");
+    assert_eq!(spec["arguments"][0]["value_name"], "FILE|-");
+    assert!(spec["variable_argument"].is_null());
+}
+
+#[test]
+fn testing_utilities() {
+    use entrance::testing::{Outcome, TryParseFrom};
+
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(informative(entrance::json_spec))]
+        Spec,
+        Level(u8),
+    }
+
+    #[derive(Arguments, Debug)]
+    struct Args {
+        num: i32,
+    }
+
+    let command = entrance::Command::<Opts, Args>::new("prog", "1.0.0");
+
+    let (opts, args) = command
+        .try_parse_from(&["prog", "--level", "2", "-3"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(opts, [Opts::Level(2)]);
+    assert_eq!(args.num, -3);
+
+    let help = command.try_parse_from(&["prog", "--help"]).unwrap();
+    assert_eq!(
+        help.output(),
+        Some(command.help_message().to_string().as_str())
+    );
+    let spec = command
+        .try_parse_from(&["prog", "--spec", "--invalid"])
+        .unwrap();
+    assert!(matches!(spec, Outcome::Informative(Opts::Spec, _)));
+    assert_eq!(
+        spec.output(),
+        Some(command.json_spec().to_string().as_str())
+    );
+
+    entrance::assert_parse_error!(command, ["prog"], Error::InvalidNumberOfArguments);
+    entrance::assert_parse_error!(
+        command,
+        ["prog", "--level"],
+        Error::MissingValue(option) if option == "--level"
+    );
+    entrance::assert_parse_error!(command, ["prog", "--level", "x", "1"], Error::ParseError(_));
+}
+
+#[test]
+fn value_names() {
+    #[derive(Options)]
+    #[allow(dead_code)]
+    enum Opts {
+        #[entrance(description = "Write to a file", short = 'o')]
+        Output(PathBuf),
+        #[entrance(description = "Read a config", value_name = "FILE")]
+        Config(String),
+        #[entrance(description = "Set a name")]
+        Name(String),
+    }
+
+    #[derive(Arguments)]
+    #[allow(dead_code)]
+    struct Args {
+        num: f64,
+        #[entrance(value_name = "PATTERN")]
+        pattern: String,
+        name: String,
+    }
+
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0")
+        .help_option(None)
+        .version_option(None);
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    sample [OPTIONS] <num: NUMBER> <PATTERN> <name>
+
+OPTIONS:
+    -o, --output <PATH>    Write to a file
+        --config <FILE>    Read a config
+        --name <VALUE>     Set a name
+
+ARGS:
+    num        
+    pattern    
+    name       
+"
+    );
+}
+
+#[test]
+fn hidden_and_deprecated() {
+    use entrance::testing::TryParseFrom;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(description = "Use colors")]
+        Color,
+        #[entrance(description = "Use colours", hidden)]
+        #[entrance(deprecated = "use --color instead")]
+        #[entrance(alias = "colours", short_alias = 'U')]
+        Colour,
+        #[entrance(description = "Debug the parser", hidden)]
+        Debug,
+    }
+
+    let command = entrance::Command::<Opts, ()>::new("sample", "1.0.0")
+        .version_option(None)
+        .warning_sink(|warning| WARNINGS.with(|w| w.borrow_mut().push(warning.to_string())));
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    sample [OPTIONS]
+
+OPTIONS:
+        --color       Use colors
+    -h, --help        Print help information
+        --help-all    Print help information including hidden options
+"
+    );
+
+    let help_all = command.try_parse_from(&["sample", "--help-all"]).unwrap();
+    assert_eq!(
+        help_all.output(),
+        Some(
+            "\
+USAGE:
+    sample [OPTIONS]
+
+OPTIONS:
+        --color       Use colors
+        --colour      Use colours
+        --debug       Debug the parser
+    -h, --help        Print help information
+        --help-all    Print help information including hidden options
+"
+        )
+    );
+
+    let (opts, ()) = command
+        .try_parse_from(&["sample", "--colour", "--debug", "--colours", "-U"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        opts,
+        [Opts::Colour, Opts::Debug, Opts::Colour, Opts::Colour]
+    );
+    WARNINGS.with(|w| {
+        assert_eq!(
+            *w.borrow(),
+            [
+                "option `--colour` is deprecated: use --color instead",
+                "option `--colours` is deprecated: use --color instead",
+                "option `-U` is deprecated: use --color instead",
+            ]
+        )
+    });
+
+    let spec: serde_json::Value = serde_json::from_str(&command.json_spec().to_string()).unwrap();
+    assert_eq!(spec["options"][1]["hidden"], true);
+    assert_eq!(spec["options"][1]["deprecated"], "use --color instead");
+}
+
+#[test]
+fn deprecated_aliases_and_arguments() {
+    use entrance::testing::TryParseFrom;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(description = "Use colors", deprecated_alias = "colour")]
+        #[entrance(deprecated_short_alias = 'C')]
+        Color,
+    }
+
+    #[derive(Arguments)]
+    struct Args {
+        #[entrance(description = "An input file")]
+        input: String,
+        #[entrance(description = "An output file", deprecated = "use --output instead")]
+        output: String,
+        #[entrance(description = "Internal use", hidden, variable_argument)]
+        rest: Vec<String>,
+    }
+
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0")
+        .version_option(None)
+        .warning_sink(|warning| WARNINGS.with(|w| w.borrow_mut().push(warning.to_string())));
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    sample [OPTIONS] <input> <output>
+
+OPTIONS:
+        --color       Use colors
+    -h, --help        Print help information
+        --help-all    Print help information including hidden options
+
+ARGS:
+    input     An input file
+    output    An output file
+"
+    );
+    let help_all = command.try_parse_from(&["sample", "--help-all"]).unwrap();
+    assert!(help_all
+        .output()
+        .unwrap()
+        .contains("<input> <output> [rest]...\n"));
+
+    let (opts, args) = command
+        .try_parse_from(&["sample", "--color", "--colour", "-C", "a", "b", "c"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(opts, [Opts::Color, Opts::Color, Opts::Color]);
+    assert_eq!((args.input.as_str(), args.output.as_str()), ("a", "b"));
+    assert_eq!(args.rest, ["c"]);
+    WARNINGS.with(|w| {
+        assert_eq!(
+            *w.borrow(),
+            [
+                "option `--colour` is deprecated: use `--color` instead",
+                "option `-C` is deprecated: use `--color` instead",
+                "argument `output` is deprecated: use --output instead",
+            ]
+        )
+    });
+
+    let spec: serde_json::Value = serde_json::from_str(&command.json_spec().to_string()).unwrap();
+    assert_eq!(spec["options"][0]["deprecated_aliases"][0], "colour");
+    assert_eq!(spec["arguments"][1]["deprecated"], "use --output instead");
+    assert_eq!(spec["variable_argument"]["hidden"], true);
+}
+
+#[test]
+fn aliases() {
+    use entrance::testing::TryParseFrom;
+
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(description = "Use colors", alias = "colour")]
+        #[entrance(visible_alias = "colors", visible_short_alias = 'C')]
+        Color,
+        #[entrance(description = "Do nothing", short = 'n', short_alias = 'N')]
+        #[entrance(long = "dry-run", alias = "noop")]
+        DryRun,
+    }
+
+    let command = entrance::Command::<Opts, ()>::new("sample", "1.0.0")
+        .help_option(None)
+        .version_option(None);
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    sample [OPTIONS]
+
+OPTIONS:
+        --color      Use colors [aliases: --colors, -C]
+    -n, --dry-run    Do nothing
+"
+    );
+
+    let (opts, ()) = command
+        .try_parse_from(&["sample", "--colour", "--colors", "-CN", "--noop"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        opts,
+        [
+            Opts::Color,
+            Opts::Color,
+            Opts::Color,
+            Opts::DryRun,
+            Opts::DryRun
+        ]
+    );
+}
+
+#[test]
+fn variable_argument_position() -> entrance::Result<()> {
+    #[derive(Arguments, Debug)]
+    struct Args {
+        #[entrance(description = "Source files", variable_argument, min = 1)]
+        src: Vec<PathBuf>,
+        #[entrance(description = "Destination")]
+        dest: PathBuf,
+    }
+
+    #[derive(Arguments, Debug)]
+    #[allow(dead_code)]
+    struct Bounded {
+        first: String,
+        #[entrance(variable_argument, min = 2, max = 3)]
+        rest: Vec<u32>,
+    }
+
+    let parse = |args: &[&str]| Args::parse(&mut args.iter().map(|s| s.to_string()));
+    let args = parse(&["a", "b", "dir"])?;
+    assert_eq!(args.src, [PathBuf::from("a"), PathBuf::from("b")]);
+    assert_eq!(args.dest, PathBuf::from("dir"));
+    assert!(matches!(
+        parse(&["dir"]),
+        Err(Error::InvalidNumberOfValues(name, _)) if name == "src"
+    ));
+    assert!(matches!(parse(&[]), Err(Error::InvalidNumberOfArguments)));
+
+    let command = entrance::Command::<(), Args>::new("cp", "1.0.0")
+        .help_option(None)
+        .version_option(None);
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    cp <src: PATH>... <dest: PATH>
+
+ARGS:
+    src     Source files
+    dest    Destination
+"
+    );
+
+    let bounded = |args: &[&str]| Bounded::parse(&mut args.iter().map(|s| s.to_string()));
+    assert_eq!(bounded(&["x", "1", "2"])?.rest, [1, 2]);
+    let error = bounded(&["x", "1", "2", "3", "4"]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid number of values for rest: expected at most 3, but 4 given"
+    );
+    let command = entrance::Command::<(), Bounded>::new("sample", "1.0.0")
+        .help_option(None)
+        .version_option(None);
+    assert!(command
+        .help_message()
+        .to_string()
+        .contains("sample <first> <rest: INTEGER>{2,3}"));
+    Ok(())
+}
+
+#[test]
+fn fixed_arity_fields() -> entrance::Result<()> {
+    #[derive(Arguments, Debug)]
+    struct Args {
+        #[entrance(description = "Coordinates")]
+        coords: [f64; 3],
+        #[entrance(description = "Size", value_names("width", "height"))]
+        size: (u32, u32),
+        #[entrance(variable_argument)]
+        rest: Vec<String>,
+    }
+
+    #[derive(Arguments, Debug)]
+    struct Trailing {
+        #[entrance(variable_argument)]
+        files: Vec<String>,
+        pair: (char, char),
+    }
+
+    let parse = |args: &[&str]| Args::parse(&mut args.iter().map(|s| s.to_string()));
+    let args = parse(&["1", "2.5", "-3", "640", "480", "x"])?;
+    assert_eq!(args.coords, [1.0, 2.5, -3.0]);
+    assert_eq!(args.size, (640, 480));
+    assert_eq!(args.rest, ["x"]);
+    assert!(matches!(
+        parse(&["1", "2", "3", "4"]),
+        Err(Error::InvalidNumberOfArguments)
+    ));
+
+    let trailing = Trailing::parse(&mut ["a", "b", "c"].iter().map(|s| s.to_string()))?;
+    assert_eq!(trailing.files, ["a"]);
+    assert_eq!(trailing.pair, ('b', 'c'));
+
+    let command = entrance::Command::<(), Args>::new("sample", "1.0.0")
+        .help_option(None)
+        .version_option(None);
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    sample <coords: NUMBER> <coords: NUMBER> <coords: NUMBER> <width> <height> [rest]...
+
+ARGS:
+    coords    Coordinates
+    size      Size
+    rest      
+"
+    );
+    Ok(())
+}
+
+#[test]
+fn collection_options() {
+    use entrance::testing::TryParseFrom;
+    use std::collections::BTreeSet;
+
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(description = "Add an include path", short = 'I')]
+        Include(Vec<PathBuf>),
+        #[entrance(description = "Enable features", delimiter = ',')]
+        Features(BTreeSet<String>),
+        #[entrance(description = "Build for a target", min = 1, max = 1)]
+        Target(String),
+    }
+
+    let command = entrance::Command::<Opts, ()>::new("sample", "1.0.0");
+    let (opts, ()) = command
+        .try_parse_from(&[
+            "sample",
+            "-I",
+            "a",
+            "--features=x,y",
+            "--target",
+            "wasm",
+            "--include",
+            "b",
+            "--features",
+            "x,z",
+        ])
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        opts,
+        [
+            Opts::Include(vec![PathBuf::from("a"), PathBuf::from("b")]),
+            Opts::Features(["x", "y", "z"].iter().map(|s| s.to_string()).collect()),
+            Opts::Target("wasm".to_string()),
+        ]
+    );
+
+    let err = command.try_parse_from(&["sample"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid number of occurrences of --target: expected at least 1, but 0 given"
+    );
+    let err = command
+        .try_parse_from(&["sample", "--target", "a", "--target", "b"])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid number of occurrences of --target: expected at most 1, but 2 given"
+    );
+}
+
+#[test]
+fn key_value_options() {
+    use entrance::testing::TryParseFrom;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(description = "Define a macro", short = 'D')]
+        Define(BTreeMap<String, String>),
+        #[entrance(description = "Set a limit", separator = ':', delimiter = ',')]
+        Limit(Vec<(String, u32)>),
+        #[entrance(description = "Set an environment variable", value_name = "K=V")]
+        Env(HashMap<String, String>),
+    }
+
+    let command = entrance::Command::<Opts, ()>::new("sample", "1.0.0")
+        .help_option(None)
+        .version_option(None);
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    sample [OPTIONS]
+
+OPTIONS:
+    -D, --define <KEY=VALUE>    Define a macro
+        --limit <KEY:VALUE>     Set a limit
+        --env <K=V>             Set an environment variable
+"
+    );
+
+    let (opts, ()) = command
+        .try_parse_from(&["sample", "-DA=1", "--limit", "cpu:2,mem:3", "-D", "B=x=y"])
+        .unwrap()
+        .unwrap();
+    let defines = [("A", "1"), ("B", "x=y")]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    assert_eq!(
+        opts,
+        [
+            Opts::Define(defines),
+            Opts::Limit(vec![("cpu".to_string(), 2), ("mem".to_string(), 3)]),
+        ]
+    );
+
+    let err = command
+        .try_parse_from(&["sample", "--env", "PATH"])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid value for option --env: expected `KEY=VALUE`, but `PATH` given"
+    );
+}
+
+#[test]
+fn optional_values() {
+    use entrance::testing::TryParseFrom;
+
+    #[derive(Options, Debug, PartialEq)]
+    enum Opts {
+        #[entrance(description = "Use colors", short = 'c')]
+        #[entrance(value_name = "WHEN", implicit_value = "always")]
+        Color(Option<String>),
+        #[entrance(description = "Run jobs in parallel", short = 'j')]
+        Jobs(Option<u32>),
+    }
+
+    #[derive(Arguments, Debug)]
+    struct Args {
+        #[entrance(description = "A file")]
+        file: String,
+    }
+
+    let command = entrance::Command::<Opts, Args>::new("sample", "1.0.0")
+        .help_option(None)
+        .version_option(None);
+    assert_eq!(
+        command.help_message().to_string(),
+        "\
+USAGE:
+    sample [OPTIONS] <file>
+
+OPTIONS:
+    -c, --color[=<WHEN>]      Use colors
+    -j, --jobs[=<INTEGER>]    Run jobs in parallel
+
+ARGS:
+    file    A file
+"
+    );
+
+    let (opts, args) = command
+        .try_parse_from(&["sample", "--color", "--jobs=4", "-cj", "never"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        opts,
+        [
+            Opts::Color(Some("always".to_string())),
+            Opts::Jobs(Some(4)),
+            Opts::Color(Some("always".to_string())),
+            Opts::Jobs(None),
+        ]
+    );
+    assert_eq!(args.file, "never");
+
+    let (opts, _) = command
+        .try_parse_from(&["sample", "--color=never", "file"])
+        .unwrap()
+        .unwrap();
+    assert_eq!(opts, [Opts::Color(Some("never".to_string()))]);
+}